@@ -0,0 +1,105 @@
+//! Captured Values
+//!
+//! Holds values captured from EEL responses. Events that refer to a
+//! value wait until the event capturing it has completed, even if they
+//! were dispatched at the same simulated time.
+
+use sim_types::eel_types::capture::{capture_text, json_path, parse_body};
+use std::collections::BTreeMap;
+use tokio::sync::watch;
+
+/// State of a captured value
+#[derive(Debug, Clone)]
+enum Binding {
+    /// The capturing event hasn't completed yet
+    Pending,
+
+    /// The value was captured
+    Bound(String),
+
+    /// The capturing event failed or the value wasn't in its response
+    Failed,
+}
+
+/// Values captured during an EEL replay, shared by every event
+#[derive(Debug)]
+pub struct Bindings {
+    state: watch::Sender<BTreeMap<String, Binding>>,
+}
+
+impl Bindings {
+    /// Creates a pending binding for each name that will be captured
+    pub fn new<I: IntoIterator<Item = String>>(names: I) -> Self {
+        let state = names.into_iter().map(|n| (n, Binding::Pending)).collect();
+        let (state, _) = watch::channel(state);
+        Bindings { state }
+    }
+
+    /// Stores the values an event captures from its response
+    /// # Arguments
+    /// * captures - Names and paths to capture
+    /// * body - The response body (`None` if the request failed)
+    pub fn capture(&self, captures: &BTreeMap<String, String>, body: Option<&[u8]>) {
+        if captures.is_empty() {
+            return;
+        }
+
+        // Responses that aren't JSON can still be captured whole with `$`
+        let document = body.map(parse_body);
+
+        self.state.send_modify(|state| {
+            for (name, path) in captures {
+                let value = document
+                    .as_ref()
+                    .and_then(|d| json_path(d, path).ok().flatten());
+
+                let binding = match value {
+                    Some(v) => {
+                        let text = capture_text(v);
+                        println!("Captured {} = {}", name, text);
+                        Binding::Bound(text)
+                    }
+                    None if document.is_none() => {
+                        println!("Could not capture {}, the event did not succeed", name);
+                        Binding::Failed
+                    }
+                    None => {
+                        println!("Could not capture {} from {}", name, path);
+                        Binding::Failed
+                    }
+                };
+
+                state.insert(name.clone(), binding);
+            }
+        });
+    }
+
+    /// Waits for every named value to be captured
+    ///
+    /// # Returns
+    /// The captured values, or an error naming a value that couldn't be captured
+    pub async fn wait_for(&self, names: &[String]) -> Result<BTreeMap<String, String>, String> {
+        let mut rx = self.state.subscribe();
+        loop {
+            let mut values = BTreeMap::new();
+            let mut pending = false;
+            for name in names {
+                match rx.borrow().get(name) {
+                    Some(Binding::Bound(v)) => {
+                        values.insert(name.clone(), v.clone());
+                    }
+                    Some(Binding::Pending) => pending = true,
+                    _ => return Err(format!("'{name}' could not be captured")),
+                }
+            }
+
+            if !pending {
+                return Ok(values);
+            }
+
+            if rx.changed().await.is_err() {
+                return Err("bindings were dropped".to_string());
+            }
+        }
+    }
+}
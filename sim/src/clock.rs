@@ -0,0 +1,77 @@
+//! Simulation Clock
+//!
+//! Maps simulated time onto wall-clock time. In real-time and scaled
+//! modes the simulation sleeps until an event is due; in virtual mode
+//! the clock jumps straight to the requested time.
+
+use chrono::{Duration, NaiveDateTime};
+use sim_types::cfg_types::{ClockConfig, ClockMode};
+use std::sync::Mutex;
+use tokio::time::Instant;
+
+/// Source of simulated time shared by everything in a run
+#[derive(Debug)]
+pub struct SimClock {
+    mode: ClockMode,
+    speed: f64,
+    sim_start: NaiveDateTime,
+    real_start: Instant,
+    virtual_now: Mutex<NaiveDateTime>,
+}
+
+impl SimClock {
+    /// Starts a clock at the given simulated time
+    /// # Arguments
+    /// * config - The clock mode and speed
+    /// * sim_start - The simulated time corresponding to "now"
+    pub fn new(config: &ClockConfig, sim_start: NaiveDateTime) -> Self {
+        let speed = match config.mode {
+            ClockMode::Realtime => 1.0,
+            _ => config.speed,
+        };
+
+        SimClock {
+            mode: config.mode,
+            speed,
+            sim_start,
+            real_start: Instant::now(),
+            virtual_now: Mutex::new(sim_start),
+        }
+    }
+
+    /// Whether simulated time only moves when asked to
+    pub fn is_virtual(&self) -> bool {
+        self.mode == ClockMode::Virtual
+    }
+
+    /// The current simulated time
+    pub fn now(&self) -> NaiveDateTime {
+        if self.is_virtual() {
+            return *self.virtual_now.lock().unwrap();
+        }
+
+        let real_elapsed = self.real_start.elapsed().mul_f64(self.speed);
+        self.sim_start + Duration::from_std(real_elapsed).unwrap_or_else(|_| Duration::zero())
+    }
+
+    /// Waits until the simulated time reaches `t`
+    ///
+    /// Returns immediately if `t` is in the past.
+    pub async fn sleep_until(&self, t: NaiveDateTime) {
+        if self.is_virtual() {
+            let mut now = self.virtual_now.lock().unwrap();
+            if t > *now {
+                *now = t;
+            }
+
+            return;
+        }
+
+        // Negative offsets (events before the start) are due immediately
+        let sim_offset = (t - self.sim_start)
+            .to_std()
+            .unwrap_or(std::time::Duration::ZERO);
+        let deadline = self.real_start + sim_offset.div_f64(self.speed);
+        tokio::time::sleep_until(deadline).await;
+    }
+}
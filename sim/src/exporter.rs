@@ -0,0 +1,50 @@
+//! Metrics Exporter
+//!
+//! Serves live request and customer metrics over HTTP in the Prometheus
+//! text format, so long runs can be charted while they're in progress.
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use sim_types::metrics_types::Metrics;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+/// Answers a scrape of `/metrics`
+async fn handle(req: Request<Body>, metrics: Metrics) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        let mut not_found = Response::new(Body::from("Not Found"));
+        *not_found.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(not_found);
+    }
+
+    let response = Response::builder()
+        .header("content-type", "text/plain; version=0.0.4")
+        .body(Body::from(metrics.render_prometheus()))
+        .unwrap();
+
+    Ok(response)
+}
+
+/// Serves `/metrics` on `addr` until the simulation ends
+/// # Arguments
+/// * addr - Address to listen on, e.g. `0.0.0.0:9090`
+/// * metrics - The metrics collected by the simulation
+pub async fn serve(addr: SocketAddr, metrics: Metrics) {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, metrics.clone()))) }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(make_service),
+        Err(e) => {
+            eprintln!("ERROR: Could not serve metrics on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    println!("Serving metrics on http://{}/metrics", addr);
+    if let Err(e) = server.await {
+        eprintln!("ERROR: Metrics server failed: {}", e);
+    }
+}
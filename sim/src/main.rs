@@ -1,11 +1,34 @@
 //! Simulation Tool
 
-use chrono::{Duration, NaiveDateTime, Utc};
+mod bindings;
+mod clock;
+mod exporter;
+mod report;
+mod scheduler;
+
+use bindings::Bindings;
+use chrono::{Duration, NaiveDateTime};
 use clap::Parser;
+use clock::SimClock;
 use hyper::{Body, Response};
-use rand::seq::SliceRandom;
-use sim_types::cfg_types::{customer_agent::Customer, Config};
-use sim_types::eel_types::{customer_events, Eel, EelEventType};
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use report::Report;
+use scheduler::Scheduler;
+use sim_types::cfg_types::arrivals::arrival_times;
+use sim_types::cfg_types::customer_agent::{Customer, CustomerOutcome, CustomerStatus, Funnel};
+use sim_types::cfg_types::operator_agent::Operator;
+use sim_types::cfg_types::{ClockConfig, ClockMode, Config};
+use sim_types::eel_types::expect::ExpectationResult;
+use sim_types::eel_types::{AuthorityEvent, Eel, EelEvent, EelEventType, Recorder};
+use sim_types::event_types::world::World;
+use sim_types::event_types::{Dispatcher, Targets};
+use sim_types::metrics_types::Metrics;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 pub use svc_storage_client_grpc::client::{vertiport_rpc_client::VertiportRpcClient, SearchFilter};
 
@@ -15,96 +38,571 @@ struct Args {
     /// Number of times to greet
     #[arg(short, long)]
     input: String,
+
+    /// Seed for the random number generator, overrides the config file
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// How simulation time advances (realtime, scaled or virtual)
+    #[arg(long)]
+    clock: Option<ClockMode>,
+
+    /// Simulated seconds per real second in scaled mode
+    #[arg(long)]
+    speed: Option<f64>,
+
+    /// Base URL of svc-cargo, overrides the config file
+    #[arg(long, env = "SIM_CARGO_URL")]
+    cargo_url: Option<String>,
+
+    /// Base URL of svc-rideshare, overrides the config file
+    #[arg(long, env = "SIM_RIDESHARE_URL")]
+    rideshare_url: Option<String>,
+
+    /// Base URL of svc-charter, overrides the config file
+    #[arg(long, env = "SIM_CHARTER_URL")]
+    charter_url: Option<String>,
+
+    /// Base URL of svc-assets, overrides the config file
+    #[arg(long, env = "SIM_ASSETS_URL")]
+    assets_url: Option<String>,
+
+    /// Base URL of the environment stand-in, overrides the config file
+    #[arg(long, env = "SIM_ENVIRONMENT_URL")]
+    environment_url: Option<String>,
+
+    /// Write a JSON summary of the run to this file
+    #[arg(long)]
+    report: Option<String>,
+
+    /// Write every request made during the run to this CSV file
+    #[arg(long)]
+    report_csv: Option<String>,
+
+    /// Record every event sent during the run to this EEL file
+    #[arg(long)]
+    record: Option<String>,
+
+    /// Serve live metrics in Prometheus format on this address
+    ///  (e.g. 0.0.0.0:9090)
+    #[arg(long, env = "SIM_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
 }
 
-async fn action(event: &EelEventType) -> Result<Response<Body>, ()> {
-    // Will add Weather and Civil Authority Events
-    match event {
-        EelEventType::CustomerEvent(s) => customer_events::action(s).await,
+impl Args {
+    /// Applies clock options given on the command line over `base`
+    fn clock_config(&self, base: ClockConfig) -> ClockConfig {
+        let mut config = base;
+        if let Some(speed) = self.speed {
+            // A speed on its own implies scaled mode
+            config.mode = ClockMode::Scaled;
+            config.speed = speed;
+        }
+
+        if let Some(mode) = self.clock {
+            config.mode = mode;
+        }
+
+        config
+    }
+
+    /// Applies service URLs given on the command line over `base`
+    fn targets(&self, base: Targets) -> Targets {
+        let mut targets = base;
+        if let Some(url) = &self.cargo_url {
+            targets.cargo = url.clone();
+        }
+
+        if let Some(url) = &self.rideshare_url {
+            targets.rideshare = url.clone();
+        }
+
+        if let Some(url) = &self.charter_url {
+            targets.charter = url.clone();
+        }
+
+        if let Some(url) = &self.assets_url {
+            targets.assets = url.clone();
+        }
+
+        if let Some(url) = &self.environment_url {
+            targets.environment = Some(url.clone());
+        }
+
+        targets
     }
 }
 
-async fn config_route(config: Config) -> Result<(), ()> {
+/// Something that acts during a config-driven simulation
+enum Actor {
+    /// A customer taking its next step
+    Customer(Box<Customer>),
+
+    /// An operator sending its next request
+    Operator(Box<Operator>),
+
+    /// An event from the scenario file
+    Event(EelEventType),
+
+    /// A booked customer's flight departing, by customer ID
+    Departure(String),
+
+    /// Booked customers checking whether a restriction or the weather
+    ///  affects their flight
+    CheckBooked,
+}
+
+async fn config_route(input: &str, config: Config, dispatcher: Dispatcher) -> Result<Report, ()> {
     println!("Detected config file.");
+    let wall_start = Instant::now();
+
+    // Seed the RNG so that a run can be reproduced
+    let seed = config.seed.unwrap_or_else(rand::random);
+    println!("Seed: {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
 
     // Initialize
     let sim_start_time: NaiveDateTime = config.timestamp_start;
     let sim_end_time = sim_start_time + Duration::seconds(config.duration_s.into());
-    let real_time_start = Utc::now();
+    let clock = Arc::new(SimClock::new(&config.clock, sim_start_time));
+    println!("Targets: {:?}", dispatcher.targets);
 
-    // Initialize Customers
+    // Customers all start at once unless arrivals are configured
     let n_customers = config.n_customers;
-    let mut customers: Vec<Customer> = vec![];
-    for _ in 0..n_customers {
-        let customer_type = config.customer_types.choose(&mut rand::thread_rng());
-        if customer_type.is_none() {
-            eprintln!("ERROR: Could not choose a customer type.");
+    let arrivals = if config.arrivals.is_empty() {
+        vec![sim_start_time; n_customers as usize]
+    } else {
+        arrival_times(
+            &config.arrivals,
+            sim_start_time,
+            config.duration_s,
+            n_customers,
+            &mut rng,
+        )
+    };
+
+    // Initialize Customers
+    let customer_types = config.customer_types.weights();
+    let distribution = match config.customer_types.distribution() {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("ERROR: Could not choose a customer type: {}", e);
             return Err(());
         }
+    };
+
+    println!("Scheduling {} customers.", arrivals.len());
+    let mut scheduler = Scheduler::new().with_max_in_flight(config.max_concurrency);
+    let mut mix: Vec<u32> = vec![0; customer_types.len()];
+    for arrival_time in arrivals {
+        let index = distribution.sample(&mut rng);
+        mix[index] += 1;
 
-        let c = Customer::generate(customer_type.unwrap(), sim_start_time);
+        let name = &customer_types[index].0;
+        let behavior = match config.behavior(name) {
+            Some(b) => b,
+            None => {
+                eprintln!("ERROR: No behavior for customer type '{}'.", name);
+                return Err(());
+            }
+        };
 
-        customers.push(c);
+        let c = Customer::generate(name, behavior, arrival_time, &mut rng);
+        scheduler.push(arrival_time, Actor::Customer(Box::new(c)));
     }
 
-    println!("Starting simulation.");
-    loop {
-        let real_elapsed_time = Utc::now() - real_time_start;
+    // Operators keep sending requests for the whole simulation
+    let n_operators: u32 = config.operators.iter().map(|o| o.count).sum();
+    if n_operators > 0 {
+        println!("Scheduling {} operators.", n_operators);
+    }
 
-        if sim_start_time + real_elapsed_time >= sim_end_time {
-            break;
+    for group in &config.operators {
+        for _ in 0..group.count {
+            let o = Operator::generate(group, sim_start_time, &mut rng);
+            scheduler.push(sim_start_time, Actor::Operator(Box::new(o)));
         }
+    }
 
-        // TODO Time Delays and Kickoff Times
-        for x in &mut customers {
-            x.next().await;
+    // Scripted events play out alongside the customers
+    if let Some(fname) = &config.scenario {
+        let eel = match Eel::from_filename(fname) {
+            Ok(eel) => eel,
+            Err(e) => {
+                eprintln!("ERROR: Could not parse scenario {}: {}", fname, e);
+                return Err(());
+            }
+        };
+
+        let events: Vec<EelEvent> = eel
+            .events
+            .into_iter()
+            .filter(|e| e.timestamp < sim_end_time)
+            .collect();
+
+        println!("Scheduling {} scenario events.", events.len());
+        for e in events {
+            scheduler.push(e.timestamp, Actor::Event(e.event));
         }
     }
 
+    // Customers act concurrently, each step on its own task
+    println!("Starting simulation.");
+    let finished: Arc<Mutex<Vec<Customer>>> = Arc::new(Mutex::new(vec![]));
+
+    // Booked customers wait here until their flight departs, or the
+    //  authorities step in
+    let booked: Arc<Mutex<BTreeMap<String, Box<Customer>>>> = Arc::new(Mutex::new(BTreeMap::new()));
+    let step_clock = clock.clone();
+    let step_dispatcher = dispatcher.clone();
+    let step_finished = finished.clone();
+    let step_booked = booked.clone();
+    scheduler
+        .run(&clock, move |timestamp, actor: Actor| {
+            let clock = step_clock.clone();
+            let dispatcher = step_dispatcher.clone();
+            let finished = step_finished.clone();
+            let booked = step_booked.clone();
+            async move {
+                let mut customer = match actor {
+                    Actor::Customer(c) => c,
+                    Actor::Departure(id) => match booked.lock().unwrap().remove(&id) {
+                        Some(c) => c,
+
+                        // Already gone, e.g. cancelled after an authority event
+                        None => return vec![],
+                    },
+                    Actor::Operator(mut operator) => {
+                        operator.next(clock.now(), &dispatcher).await;
+                        if operator.next_action_time() >= sim_end_time {
+                            return vec![];
+                        }
+
+                        return vec![(operator.next_action_time(), Actor::Operator(operator))];
+                    }
+                    Actor::Event(event) => {
+                        let result = dispatcher.dispatch(&event, timestamp).await;
+                        println!(
+                            "EVENT @ {}\n{}\nRESPONSE\n{:?}\n",
+                            timestamp,
+                            serde_json::to_string_pretty(&event).unwrap(),
+                            result
+                        );
+
+                        // Booked customers check whether their flight is affected,
+                        //  now and once a restriction or the weather comes into effect
+                        let start = match &event {
+                            EelEventType::WeatherEvent(w) => w.start,
+                            EelEventType::AuthorityEvent(AuthorityEvent::Tfr(t)) => t.start,
+                            EelEventType::AuthorityEvent(AuthorityEvent::VertiportClosure(c)) => {
+                                c.start
+                            }
+                            EelEventType::AuthorityEvent(_) => None,
+                            _ => return vec![],
+                        };
+
+                        let mut next = wake_booked(&booked, timestamp);
+                        if let Some(start) = start.filter(|s| *s > timestamp && *s < sim_end_time) {
+                            next.push((start, Actor::CheckBooked));
+                        }
+
+                        return next;
+                    }
+                    Actor::CheckBooked => return wake_booked(&booked, timestamp),
+                };
+
+                let was_booked = customer.status() == CustomerStatus::Booked;
+                customer.next(clock.now(), &dispatcher).await;
+
+                let next_action_time = customer.next_action_time();
+                if customer.is_done() {
+                    finished.lock().unwrap().push(*customer);
+                    return vec![];
+                }
+
+                if customer.status() == CustomerStatus::Booked {
+                    let id = customer.id();
+                    booked.lock().unwrap().insert(id.clone(), customer);
+
+                    // Customers woken early keep their departure scheduled
+                    if was_booked || next_action_time >= sim_end_time {
+                        return vec![];
+                    }
+
+                    return vec![(next_action_time, Actor::Departure(id))];
+                }
+
+                if next_action_time >= sim_end_time {
+                    dispatcher.metrics.customer_left(customer.status());
+                    finished.lock().unwrap().push(*customer);
+                    return vec![];
+                }
+
+                vec![(next_action_time, Actor::Customer(customer))]
+            }
+        })
+        .await;
+
+    // Nothing left to do but let the simulation run out
+    clock.sleep_until(sim_end_time).await;
+
+    // Customers whose flights depart after the simulation
+    let mut finished = finished.lock().unwrap();
+    for (_, customer) in std::mem::take(&mut *booked.lock().unwrap()) {
+        dispatcher.metrics.customer_left(customer.status());
+        finished.push(*customer);
+    }
+
+    let customers: Vec<CustomerOutcome> = finished.iter().map(|c| c.outcome()).collect();
+
+    print_mix(&customer_types, &mix);
+    print_funnel(&Funnel::by_customer_type(&customers));
+    print_fleet(&dispatcher.world, clock.now());
+    dispatcher.metrics.print_summary();
     println!("Done!");
-    Ok(())
+
+    let report = Report::new(
+        input,
+        &dispatcher.metrics,
+        sim_start_time,
+        clock.now(),
+        wall_start.elapsed().as_secs_f64(),
+    );
+
+    Ok(report.with_customers(config, seed, customers))
+}
+
+/// Hands every booked customer back to the scheduler to act at `t`
+fn wake_booked(
+    booked: &Mutex<BTreeMap<String, Box<Customer>>>,
+    t: NaiveDateTime,
+) -> Vec<(NaiveDateTime, Actor)> {
+    std::mem::take(&mut *booked.lock().unwrap())
+        .into_values()
+        .map(|c| (t, Actor::Customer(c)))
+        .collect()
+}
+
+/// Prints how many customers of each type were generated against the weights asked for
+fn print_mix(customer_types: &[(String, f64)], mix: &[u32]) {
+    let total_weight: f64 = customer_types.iter().map(|(_, w)| w).sum();
+    let total: u32 = mix.iter().sum();
+
+    println!("Customer mix:");
+    for ((name, weight), count) in customer_types.iter().zip(mix) {
+        let share = if total > 0 {
+            100.0 * f64::from(*count) / f64::from(total)
+        } else {
+            0.0
+        };
+
+        println!(
+            "  {:<12} {:>6} ({:5.1}%, configured {:5.1}%)",
+            name,
+            count,
+            share,
+            100.0 * weight / total_weight
+        );
+    }
 }
 
-async fn eel_route(eel: Eel) -> Result<(), ()> {
+/// Prints how far customers of each type got through booking a flight
+fn print_funnel(funnels: &BTreeMap<String, Funnel>) {
+    println!(
+        "{:<14} {:>8} {:>11} {:>8} {:>10} {:>9} {:>10} {:>8} {:>10}",
+        "FUNNEL",
+        "SPAWNED",
+        "VERTIPORTS",
+        "OPTIONS",
+        "CONFIRMED",
+        "MODIFIED",
+        "CANCELLED",
+        "KEPT",
+        "DISRUPTED"
+    );
+
+    for (name, f) in funnels {
+        println!(
+            "{:<14} {:>8} {:>11} {:>8} {:>10} {:>9} {:>10} {:>8} {:>10}",
+            name,
+            f.spawned,
+            f.got_vertiports,
+            f.got_options,
+            f.confirmed,
+            f.modified,
+            f.cancelled,
+            f.kept,
+            f.disrupted
+        );
+    }
+}
+
+async fn eel_route(
+    input: &str,
+    eel: Eel,
+    clock_config: ClockConfig,
+    dispatcher: Dispatcher,
+) -> Result<Report, ()> {
     println!("Detected EEL file.");
+    let wall_start = Instant::now();
     if eel.events.is_empty() {
         eprintln!("No events parsed from EEL file.");
+        return Err(());
+    }
+
+    // Values captured from responses, for later events to refer to
+    let bindings = Arc::new(Bindings::new(
+        eel.events.iter().flat_map(|e| e.capture.keys().cloned()),
+    ));
+
+    // Events aren't required to be in order in the file
+    let mut scheduler = Scheduler::new();
+    for (index, e) in eel.events.into_iter().enumerate() {
+        scheduler.push(e.timestamp, (index, e));
     }
 
-    let sim_time_start = eel.events[0].timestamp;
-    let real_time_start = Utc::now();
-    println!("Sim Time Start: {:?}\n", sim_time_start);
+    let sim_time_start = scheduler.next_due().unwrap();
+    let clock = SimClock::new(&clock_config, sim_time_start);
+    println!("Sim Time Start: {:?}", sim_time_start);
+    println!("Targets: {:?}\n", dispatcher.targets);
 
-    // Start from first event
-    let mut event_iter = eel.events.iter();
-    let mut next = event_iter.next();
+    let results: Arc<Mutex<Vec<ExpectationResult>>> = Arc::new(Mutex::new(vec![]));
+    scheduler
+        .run(&clock, |timestamp, (index, e): (usize, EelEvent)| {
+            let dispatcher = dispatcher.clone();
+            let bindings = bindings.clone();
+            let results = results.clone();
+            async move {
+                let values = bindings.wait_for(&e.references()).await;
+                let (event, expect) = match values.and_then(|v| e.resolve(&v)) {
+                    Ok(resolved) => resolved,
+                    Err(err) => {
+                        println!("EVENT @ {}\nSkipped: {}\n", timestamp, err);
+                        bindings.capture(&e.capture, None);
+                        if e.expect.is_some() {
+                            results.lock().unwrap().push(ExpectationResult {
+                                index,
+                                timestamp,
+                                passed: false,
+                                failures: vec![format!("skipped: {}", err)],
+                            });
+                        }
+
+                        return vec![];
+                    }
+                };
+
+                let started = Instant::now();
+                let result = dispatcher.dispatch(&event, timestamp).await;
+                let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+                let (result, status, bytes) = match result {
+                    Ok(resp) => {
+                        let (parts, body) = resp.into_parts();
+                        let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+                        let status = parts.status;
+                        (
+                            Ok(Response::from_parts(parts, Body::from(bytes.clone()))),
+                            Some(status),
+                            Some(bytes),
+                        )
+                    }
+                    Err(e) => (Err(e), None, None),
+                };
+
+                if matches!(status, Some(s) if s.is_success()) {
+                    bindings.capture(&e.capture, bytes.as_deref());
+                } else {
+                    bindings.capture(&e.capture, None);
+                }
 
-    loop {
-        match next {
-            None => break,
-            Some(e) => {
-                let elapsed_time = Utc::now() - real_time_start;
-                let sim_time = sim_time_start + elapsed_time;
+                let mut checked = String::new();
+                if let Some(expect) = &expect {
+                    let failures =
+                        expect.check(status.map(|s| s.as_u16()), bytes.as_deref(), latency_ms);
+                    checked = if failures.is_empty() {
+                        "EXPECTED: PASS\n".to_string()
+                    } else {
+                        format!("EXPECTED: FAIL\n  {}\n", failures.join("\n  "))
+                    };
 
-                if e.timestamp > sim_time {
-                    continue;
+                    results.lock().unwrap().push(ExpectationResult {
+                        index,
+                        timestamp,
+                        passed: failures.is_empty(),
+                        failures,
+                    });
                 }
 
+                // Print together so concurrent events don't interleave
                 println!(
-                    "EVENT @ {}\n{}",
-                    e.timestamp,
-                    serde_json::to_string_pretty(&e.event).unwrap()
+                    "EVENT @ {}\n{}\nRESPONSE\n{:?}\n{}",
+                    timestamp,
+                    serde_json::to_string_pretty(&event).unwrap(),
+                    result,
+                    checked
                 );
-                let result = action(&e.event).await;
-                println!("RESPONSE\n{:?}\n", result);
-                next = event_iter.next();
+
+                vec![]
             }
-        }
-    }
+        })
+        .await;
 
     println!("End of EEL file! Simulation over.");
+    print_fleet(&dispatcher.world, clock.now());
+    dispatcher.metrics.print_summary();
 
-    Ok(())
+    let mut results = results.lock().unwrap().clone();
+    results.sort_by_key(|r| r.index);
+    print_expectations(&results);
+
+    let report = Report::new(
+        input,
+        &dispatcher.metrics,
+        sim_time_start,
+        clock.now(),
+        wall_start.elapsed().as_secs_f64(),
+    );
+
+    Ok(report.with_expectations(results))
+}
+
+/// Prints how much of the fleet can fly, if any aircraft events occurred
+fn print_fleet(world: &World, t: NaiveDateTime) {
+    let (total, available) = world.fleet_availability(t);
+    if total > 0 {
+        println!("Fleet: {available} of {total} aircraft available");
+    }
+}
+
+/// Prints which events got the responses they expected
+fn print_expectations(results: &[ExpectationResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    let passed = results.iter().filter(|r| r.passed).count();
+    println!(
+        "Expectations: {} passed, {} failed",
+        passed,
+        results.len() - passed
+    );
+
+    for r in results.iter().filter(|r| !r.passed) {
+        println!(
+            "  FAIL event {} @ {}: {}",
+            r.index,
+            r.timestamp,
+            r.failures.join("; ")
+        );
+    }
+}
+
+/// Fails fast if a service URL from the command line or environment
+///  can't be used
+fn check_targets(targets: &Targets) -> Result<(), ()> {
+    targets.validate().map_err(|e| {
+        eprintln!("ERROR: {}", e);
+    })
 }
 
 /// Start a simulation from a file.
@@ -113,18 +611,123 @@ async fn eel_route(eel: Eel) -> Result<(), ()> {
 /// ```
 /// cargo run -p sim -- -i samples/cfg.yaml
 /// cargo run -p sim -- -i samples/eel.json
+/// cargo run -p sim -- -i samples/eel.json --clock virtual
 /// ```
 #[tokio::main]
 async fn main() -> Result<(), ()> {
     let args = Args::parse();
+    if let Some(speed) = args.speed {
+        if speed <= 0.0 || !speed.is_finite() {
+            eprintln!("Clock speed must be positive, got {}.", speed);
+            return Err(());
+        }
+    }
+
+    let metrics = Metrics::new();
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(exporter::serve(addr, metrics.clone()));
+    }
+
+    let recorder = args.record.as_ref().map(|_| Recorder::new());
 
-    let fname = args.input;
-    if let Ok(eel) = Eel::from_filename(&fname) {
-        eel_route(eel).await
-    } else if let Ok(config) = Config::from_filename(&fname) {
-        config_route(config).await
+    let fname = &args.input;
+    let mut report = if let Ok(eel) = Eel::parse_filename(fname) {
+        // The input is an EEL, so report why it isn't a valid one
+        if let Err(e) = eel.validate() {
+            eprintln!("ERROR: Invalid EEL file {}: {}", fname, e);
+            return Err(());
+        }
+
+        let targets = args.targets(Targets::default());
+        check_targets(&targets)?;
+        eel_route(
+            fname,
+            eel,
+            args.clock_config(ClockConfig::default()),
+            Dispatcher::new(targets, metrics).with_recorder(recorder.clone()),
+        )
+        .await?
     } else {
-        eprintln!("Could not parse input as an EEL or Config file.");
-        Err(())
+        let mut config = match Config::parse_filename(fname) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Could not parse input as an EEL or Config file: {}", e);
+                return Err(());
+            }
+        };
+
+        if args.seed.is_some() {
+            config.seed = args.seed;
+        }
+
+        // Validate once the command line has had its say, so that it can
+        //  fix a value from the file
+        config.clock = args.clock_config(config.clock);
+        config.targets = args.targets(config.targets);
+        if let Err(e) = config.validate() {
+            eprintln!("ERROR: Invalid config file {}: {}", fname, e);
+            return Err(());
+        }
+
+        let dispatcher =
+            Dispatcher::new(config.targets.clone(), metrics).with_recorder(recorder.clone());
+        config_route(fname, config, dispatcher).await?
+    };
+
+    let slo = match &report.config {
+        Some(config) => config.slo.clone(),
+        None => vec![],
+    };
+    let slo_passed = report.check_slo(&slo);
+    report.print_slo();
+
+    if let Some(path) = &args.report {
+        if let Err(e) = report.write_json(path) {
+            eprintln!("ERROR: Could not write report to {}: {}", path, e);
+            return Err(());
+        }
+
+        println!("Wrote report to {}.", path);
+    }
+
+    if let Some(path) = &args.report_csv {
+        if let Err(e) = report.write_csv(path) {
+            eprintln!("ERROR: Could not write request CSV to {}: {}", path, e);
+            return Err(());
+        }
+
+        println!("Wrote requests to {}.", path);
+    }
+
+    if let (Some(path), Some(recorder)) = (&args.record, &recorder) {
+        let eel = recorder.eel();
+        if let Err(e) = eel.to_filename(path) {
+            eprintln!("ERROR: Could not write EEL to {}: {}", path, e);
+            return Err(());
+        }
+
+        println!("Recorded {} events to {}.", eel.events.len(), path);
+    }
+
+    let failed = report.expectations.iter().filter(|r| !r.passed).count();
+    if failed > 0 {
+        eprintln!(
+            "ERROR: {} of {} events did not get the expected response.",
+            failed,
+            report.expectations.len()
+        );
+        return Err(());
     }
+
+    if !slo_passed {
+        let failed = report.slo.iter().filter(|r| !r.passed).count();
+        eprintln!(
+            "ERROR: {} of {} SLO assertions failed.",
+            failed,
+            report.slo.len()
+        );
+        return Err(());
+    }
+
+    Ok(())
 }
@@ -0,0 +1,206 @@
+//! Run Report
+//!
+//! A machine-readable summary of a simulation, written as JSON, and an
+//! optional CSV with one row per request, so runs can be compared by CI
+//! jobs and notebooks.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sim_types::cfg_types::customer_agent::{CustomerOutcome, CustomerStatus, Funnel};
+use sim_types::cfg_types::slo::{SloAssertion, SloResult};
+use sim_types::cfg_types::Config;
+use sim_types::eel_types::expect::ExpectationResult;
+use sim_types::metrics_types::{EndpointSummary, Metrics, RequestRecord};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Total requests sent during a run
+#[derive(Debug, Serialize)]
+pub struct RequestTotals {
+    /// Number of requests sent
+    pub count: usize,
+
+    /// Number of requests that failed or returned an error status
+    pub errors: usize,
+}
+
+/// Summary of a simulation run
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// The file the simulation was started from
+    pub input: String,
+
+    /// The configuration used, including command line overrides
+    ///  (`None` for EEL runs)
+    pub config: Option<Config>,
+
+    /// Seed of the random number generator (`None` for EEL runs)
+    pub seed: Option<u64>,
+
+    /// Simulation time at which the run started
+    pub sim_start: NaiveDateTime,
+
+    /// Simulation time at which the run ended
+    pub sim_end: NaiveDateTime,
+
+    /// Real time the run took (seconds)
+    pub wall_duration_s: f64,
+
+    /// Total requests sent
+    pub requests: RequestTotals,
+
+    /// Latency and error statistics for each endpoint
+    pub endpoints: Vec<EndpointSummary>,
+
+    /// Number of customers in each final phase
+    pub outcomes: BTreeMap<CustomerStatus, usize>,
+
+    /// How far customers of each type got through booking a flight
+    pub funnel: BTreeMap<String, Funnel>,
+
+    /// Where each customer ended up
+    pub customers: Vec<CustomerOutcome>,
+
+    /// Outcome of each SLO assertion in the configuration
+    pub slo: Vec<SloResult>,
+
+    /// Outcome of each expectation in the EEL file
+    pub expectations: Vec<ExpectationResult>,
+
+    /// Every request sent, for the CSV report
+    #[serde(skip)]
+    pub records: Vec<RequestRecord>,
+}
+
+impl Report {
+    /// Summarizes a run from its collected metrics
+    /// # Arguments
+    /// * input - The file the simulation was started from
+    /// * metrics - Requests recorded during the run
+    /// * sim_start - Simulation time at which the run started
+    /// * sim_end - Simulation time at which the run ended
+    /// * wall_duration_s - Real time the run took (seconds)
+    pub fn new(
+        input: &str,
+        metrics: &Metrics,
+        sim_start: NaiveDateTime,
+        sim_end: NaiveDateTime,
+        wall_duration_s: f64,
+    ) -> Self {
+        let records = metrics.records();
+        Report {
+            input: input.to_string(),
+            config: None,
+            seed: None,
+            sim_start,
+            sim_end,
+            wall_duration_s,
+            requests: RequestTotals {
+                count: records.len(),
+                errors: records.iter().filter(|r| r.is_error()).count(),
+            },
+            endpoints: metrics.summary(),
+            outcomes: BTreeMap::new(),
+            funnel: BTreeMap::new(),
+            customers: vec![],
+            slo: vec![],
+            expectations: vec![],
+            records,
+        }
+    }
+
+    /// Adds the configuration and customers of a config-driven run
+    ///
+    /// The seed is written back into the config so it can be rerun as is.
+    pub fn with_customers(
+        mut self,
+        mut config: Config,
+        seed: u64,
+        customers: Vec<CustomerOutcome>,
+    ) -> Self {
+        for c in &customers {
+            *self.outcomes.entry(c.status).or_default() += 1;
+        }
+
+        self.funnel = Funnel::by_customer_type(&customers);
+        config.seed = Some(seed);
+        self.config = Some(config);
+        self.seed = Some(seed);
+        self.customers = customers;
+        self
+    }
+
+    /// Adds the results of an EEL file's expectations
+    pub fn with_expectations(mut self, expectations: Vec<ExpectationResult>) -> Self {
+        self.expectations = expectations;
+        self
+    }
+
+    /// Checks SLO assertions against the run
+    ///
+    /// # Returns
+    /// true if every assertion passed
+    pub fn check_slo(&mut self, assertions: &[SloAssertion]) -> bool {
+        self.slo = assertions
+            .iter()
+            .map(|a| a.evaluate(&self.records, &self.funnel))
+            .collect();
+
+        self.slo.iter().all(|r| r.passed)
+    }
+
+    /// Prints whether each SLO assertion passed
+    pub fn print_slo(&self) {
+        if self.slo.is_empty() {
+            return;
+        }
+
+        println!("SLO:");
+        for r in &self.slo {
+            let actual = match r.actual {
+                Some(a) => format!("{:.3}", a),
+                None => "no data".to_string(),
+            };
+
+            println!(
+                "  {} {} (actual: {})",
+                if r.passed { "PASS" } else { "FAIL" },
+                r.assertion,
+                actual
+            );
+        }
+    }
+
+    /// Writes the report as JSON
+    pub fn write_json(&self, path: &str) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Writes every request as a row of a CSV file
+    pub fn write_csv(&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "sim_time,wall_time,endpoint,method,status,latency_ms,response_bytes"
+        )?;
+
+        for r in &self.records {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{:.3},{}",
+                r.sim_time,
+                r.wall_time.to_rfc3339(),
+                r.endpoint,
+                r.method,
+                r.status.map(|s| s.to_string()).unwrap_or_default(),
+                r.latency_ms,
+                r.response_bytes
+            )?;
+        }
+
+        writer.flush()
+    }
+}
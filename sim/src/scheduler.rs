@@ -0,0 +1,271 @@
+//! Event Scheduler
+//!
+//! Holds pending work in a priority queue ordered by simulated time,
+//! sleeps on the [`SimClock`] until the earliest item is due and hands
+//! it to a dispatcher running on its own tokio task, so one slow
+//! response doesn't hold up the events behind it. A dispatcher can hand
+//! its item back to be run again later, which is how agents take turns,
+//! or queue other work, such as waking agents that were set aside.
+
+use crate::clock::SimClock;
+use chrono::NaiveDateTime;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::future::Future;
+use tokio::task::{JoinError, JoinSet};
+
+/// An item waiting in the queue
+#[derive(Debug)]
+struct Scheduled<T> {
+    time: NaiveDateTime,
+
+    /// Insertion order, so items due at the same time keep their order
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Scheduled<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Scheduled<T> {}
+
+impl<T> PartialOrd for Scheduled<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scheduled<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+/// Priority queue of work keyed by simulated time
+#[derive(Debug)]
+pub struct Scheduler<T> {
+    queue: BinaryHeap<Reverse<Scheduled<T>>>,
+    seq: u64,
+
+    /// Most items dispatched at once (unlimited if `None`)
+    max_in_flight: Option<usize>,
+}
+
+impl<T: Send + 'static> Default for Scheduler<T> {
+    fn default() -> Self {
+        Scheduler {
+            queue: BinaryHeap::new(),
+            seq: 0,
+            max_in_flight: None,
+        }
+    }
+}
+
+impl<T: Send + 'static> Scheduler<T> {
+    /// Creates an empty scheduler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits how many items can be dispatched at once
+    ///
+    /// Due items wait for a running dispatch to finish once the limit
+    ///  is reached.
+    pub fn with_max_in_flight(mut self, max_in_flight: Option<usize>) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Queues an item to be dispatched at simulated time `time`
+    pub fn push(&mut self, time: NaiveDateTime, item: T) {
+        self.queue.push(Reverse(Scheduled {
+            time,
+            seq: self.seq,
+            item,
+        }));
+        self.seq += 1;
+    }
+
+    /// The time of the earliest queued item
+    pub fn next_due(&self) -> Option<NaiveDateTime> {
+        self.queue.peek().map(|Reverse(s)| s.time)
+    }
+
+    /// Dispatches every queued item at its scheduled time
+    ///
+    /// Each item is dispatched on its own task. Every item the dispatch
+    ///  returns, with its time, is queued, so an item can be handed back
+    ///  to run again later. In virtual mode the
+    ///  clock only moves forward once every dispatched task has finished,
+    ///  so responses arrive "instantly" in simulated time.
+    /// # Arguments
+    /// * clock - The simulation clock to schedule against
+    /// * dispatch - Called with each item and its scheduled time
+    pub async fn run<F, Fut>(mut self, clock: &SimClock, mut dispatch: F)
+    where
+        F: FnMut(NaiveDateTime, T) -> Fut,
+        Fut: Future<Output = Vec<(NaiveDateTime, T)>> + Send + 'static,
+    {
+        let mut in_flight: JoinSet<Vec<(NaiveDateTime, T)>> = JoinSet::new();
+
+        loop {
+            let next_due = match self.next_due() {
+                Some(t) => t,
+                None if in_flight.is_empty() => break,
+                None => {
+                    // Dispatches may still hand back work
+                    let result = in_flight.join_next().await;
+                    self.reap(result);
+                    continue;
+                }
+            };
+
+            let at_capacity = matches!(self.max_in_flight, Some(max) if in_flight.len() >= max);
+
+            if next_due <= clock.now() && !at_capacity {
+                let Reverse(s) = self.queue.pop().unwrap();
+                in_flight.spawn(dispatch(s.time, s.item));
+                continue;
+            }
+
+            if in_flight.is_empty() {
+                clock.sleep_until(next_due).await;
+                continue;
+            }
+
+            if at_capacity || clock.is_virtual() {
+                // Let outstanding work finish before jumping ahead
+                let result = in_flight.join_next().await;
+                self.reap(result);
+                continue;
+            }
+
+            tokio::select! {
+                _ = clock.sleep_until(next_due) => {},
+                result = in_flight.join_next() => self.reap(result),
+            }
+        }
+    }
+
+    /// Queues work handed back by a finished dispatch
+    fn reap(&mut self, result: Option<Result<Vec<(NaiveDateTime, T)>, JoinError>>) {
+        match result {
+            Some(Ok(items)) => {
+                for (time, item) in items {
+                    self.push(time, item);
+                }
+            }
+            Some(Err(e)) => eprintln!("ERROR: Dispatch task failed: {:?}", e),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sim_types::cfg_types::{ClockConfig, ClockMode};
+    use sim_types::test_util::at;
+    use std::sync::{Arc, Mutex};
+
+    fn virtual_clock() -> SimClock {
+        let config = ClockConfig {
+            mode: ClockMode::Virtual,
+            speed: 1.0,
+        };
+
+        SimClock::new(&config, at(0))
+    }
+
+    #[tokio::test]
+    async fn dispatches_in_time_order() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(at(30), "c");
+        scheduler.push(at(10), "a");
+        scheduler.push(at(20), "b");
+        assert_eq!(scheduler.next_due(), Some(at(10)));
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let log = seen.clone();
+        scheduler
+            .run(&virtual_clock(), move |t, item| {
+                log.lock().unwrap().push((t, item));
+                async { vec![] }
+            })
+            .await;
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(at(10), "a"), (at(20), "b"), (at(30), "c")]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_insertion_order_at_the_same_time() {
+        let mut scheduler = Scheduler::new().with_max_in_flight(Some(1));
+        for i in 0..5 {
+            scheduler.push(at(0), i);
+        }
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let log = seen.clone();
+        scheduler
+            .run(&virtual_clock(), move |_, item| {
+                log.lock().unwrap().push(item);
+                async { vec![] }
+            })
+            .await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn requeues_handed_back_items() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(at(0), 3u32);
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let log = seen.clone();
+        scheduler
+            .run(&virtual_clock(), move |t, remaining| {
+                log.lock().unwrap().push(t);
+                async move {
+                    match remaining {
+                        0 => vec![],
+                        n => vec![(t + chrono::Duration::seconds(5), n - 1)],
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![at(0), at(5), at(10), at(15)]);
+    }
+
+    #[tokio::test]
+    async fn queues_every_item_handed_back() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(at(0), 0u32);
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let log = seen.clone();
+        scheduler
+            .run(&virtual_clock(), move |t, item| {
+                log.lock().unwrap().push((t, item));
+                async move {
+                    match item {
+                        0 => vec![(at(10), 2), (at(5), 1)],
+                        _ => vec![],
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(at(0), 0), (at(5), 1), (at(10), 2)]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use chrono::{Duration, NaiveDateTime, Timelike};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How customers enter the simulation over time
+///
+/// Rates are in customers per minute.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArrivalProcess {
+    /// Customers arrive at random at a constant average rate
+    Poisson {
+        /// Average arrivals per minute
+        rate_per_min: f64,
+    },
+
+    /// The arrival rate changes linearly between two points in time
+    Ramp {
+        /// Arrivals per minute at `start_s`
+        from_per_min: f64,
+
+        /// Arrivals per minute at `end_s`
+        to_per_min: f64,
+
+        /// Seconds into the simulation at which the ramp starts
+        #[serde(default)]
+        start_s: u32,
+
+        /// Seconds into the simulation at which the ramp ends
+        ///  (end of the simulation if not provided)
+        #[serde(default)]
+        end_s: Option<u32>,
+    },
+
+    /// A group of customers arrives at once
+    Burst {
+        /// Seconds into the simulation at which the burst occurs
+        at_s: u32,
+
+        /// Number of customers in the burst
+        count: u32,
+
+        /// Seconds over which the burst is spread (all at once if not provided)
+        #[serde(default)]
+        spread_s: u32,
+    },
+
+    /// The arrival rate follows the hour of the (simulated) day
+    TimeOfDay {
+        /// Arrivals per minute for each hour of the day, starting at midnight
+        rates_per_min: Vec<f64>,
+    },
+}
+
+impl ArrivalProcess {
+    /// Checks that the process makes sense for a simulation of `duration_s`
+    pub fn validate(&self, duration_s: u32) -> Result<(), String> {
+        let check_rate = |rate: f64| {
+            if rate < 0.0 || !rate.is_finite() {
+                Err(format!("arrival rate must be zero or positive, got {rate}"))
+            } else {
+                Ok(())
+            }
+        };
+
+        match self {
+            ArrivalProcess::Poisson { rate_per_min } => check_rate(*rate_per_min),
+            ArrivalProcess::Ramp {
+                from_per_min,
+                to_per_min,
+                start_s,
+                end_s,
+            } => {
+                check_rate(*from_per_min)?;
+                check_rate(*to_per_min)?;
+                let end_s = end_s.unwrap_or(duration_s);
+                if end_s > duration_s {
+                    return Err(format!(
+                        "ramp ends at {end_s}s, after the end of the simulation ({duration_s}s)"
+                    ));
+                }
+
+                if *start_s >= end_s {
+                    return Err(format!(
+                        "ramp must start ({start_s}s) before it ends ({end_s}s)"
+                    ));
+                }
+
+                Ok(())
+            }
+            ArrivalProcess::Burst { at_s, .. } => {
+                if *at_s >= duration_s {
+                    return Err(format!(
+                        "burst at {at_s}s is after the end of the simulation ({duration_s}s)"
+                    ));
+                }
+
+                Ok(())
+            }
+            ArrivalProcess::TimeOfDay { rates_per_min } => {
+                if rates_per_min.len() != 24 {
+                    return Err(format!(
+                        "time_of_day needs 24 hourly rates, got {}",
+                        rates_per_min.len()
+                    ));
+                }
+
+                rates_per_min.iter().try_for_each(|r| check_rate(*r))
+            }
+        }
+    }
+
+    /// Arrivals per second, `offset_s` seconds into the simulation
+    fn rate_at(&self, start: NaiveDateTime, duration_s: u32, offset_s: f64) -> f64 {
+        match self {
+            ArrivalProcess::Poisson { rate_per_min } => rate_per_min / 60.0,
+            ArrivalProcess::Ramp {
+                from_per_min,
+                to_per_min,
+                start_s,
+                end_s,
+            } => {
+                let start_s = f64::from(*start_s);
+                let end_s = f64::from(end_s.unwrap_or(duration_s));
+                if offset_s < start_s || offset_s >= end_s {
+                    return 0.0;
+                }
+
+                let progress = (offset_s - start_s) / (end_s - start_s);
+                (from_per_min + (to_per_min - from_per_min) * progress) / 60.0
+            }
+            ArrivalProcess::TimeOfDay { rates_per_min } => {
+                let t = start + Duration::milliseconds((offset_s * 1000.0) as i64);
+                rates_per_min[t.hour() as usize] / 60.0
+            }
+            ArrivalProcess::Burst { .. } => 0.0,
+        }
+    }
+
+    /// The highest rate the process reaches (per second)
+    fn max_rate(&self) -> f64 {
+        match self {
+            ArrivalProcess::Poisson { rate_per_min } => rate_per_min / 60.0,
+            ArrivalProcess::Ramp {
+                from_per_min,
+                to_per_min,
+                ..
+            } => from_per_min.max(*to_per_min) / 60.0,
+            ArrivalProcess::TimeOfDay { rates_per_min } => {
+                rates_per_min.iter().cloned().fold(0.0, f64::max) / 60.0
+            }
+            ArrivalProcess::Burst { .. } => 0.0,
+        }
+    }
+
+    /// Draws arrival offsets (seconds from the start of the simulation)
+    /// # Arguments
+    /// * start - Simulated time at which the simulation starts
+    /// * duration_s - Length of the simulation in seconds
+    /// * rng - Random number generator to draw from
+    pub fn sample(&self, start: NaiveDateTime, duration_s: u32, rng: &mut StdRng) -> Vec<f64> {
+        let duration = f64::from(duration_s);
+
+        if let ArrivalProcess::Burst {
+            at_s,
+            count,
+            spread_s,
+        } = self
+        {
+            let at_s = f64::from(*at_s);
+            let spread_s = f64::from(*spread_s);
+            return (0..*count)
+                .map(|_| at_s + rng.gen::<f64>() * spread_s)
+                .filter(|t| *t < duration)
+                .collect();
+        }
+
+        // Thinning: draw from a Poisson process at the maximum rate
+        //  and keep each arrival with probability rate(t) / max_rate
+        let max_rate = self.max_rate();
+        if max_rate <= 0.0 {
+            return vec![];
+        }
+
+        let mut arrivals = vec![];
+        let mut t = 0.0;
+        loop {
+            // Exponential inter-arrival times
+            t += -(1.0 - rng.gen::<f64>()).ln() / max_rate;
+            if t >= duration {
+                break;
+            }
+
+            if rng.gen::<f64>() * max_rate < self.rate_at(start, duration_s, t) {
+                arrivals.push(t);
+            }
+        }
+
+        arrivals
+    }
+}
+
+/// Draws arrival times for all processes combined, earliest first
+/// # Arguments
+/// * processes - The arrival processes to combine
+/// * start - Simulated time at which the simulation starts
+/// * duration_s - Length of the simulation in seconds
+/// * max_customers - The most arrivals to return
+/// * rng - Random number generator to draw from
+pub fn arrival_times(
+    processes: &[ArrivalProcess],
+    start: NaiveDateTime,
+    duration_s: u32,
+    max_customers: u32,
+    rng: &mut StdRng,
+) -> Vec<NaiveDateTime> {
+    let mut offsets: Vec<f64> = processes
+        .iter()
+        .flat_map(|p| p.sample(start, duration_s, rng))
+        .collect();
+
+    offsets.sort_by(|a, b| a.total_cmp(b));
+    offsets
+        .into_iter()
+        .take(max_customers as usize)
+        .map(|t| start + Duration::milliseconds((t * 1000.0) as i64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn start() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2022-01-01T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn ramp_must_end_within_the_simulation() {
+        let ramp = |start_s, end_s| ArrivalProcess::Ramp {
+            from_per_min: 0.0,
+            to_per_min: 10.0,
+            start_s,
+            end_s,
+        };
+
+        assert!(ramp(0, None).validate(600).is_ok());
+        assert!(ramp(60, Some(600)).validate(600).is_ok());
+        assert!(ramp(0, Some(601)).validate(600).is_err());
+        assert!(ramp(600, None).validate(600).is_err());
+        assert!(ramp(300, Some(200)).validate(600).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_processes() {
+        let poisson = ArrivalProcess::Poisson { rate_per_min: -1.0 };
+        assert!(poisson.validate(600).is_err());
+
+        let burst = ArrivalProcess::Burst {
+            at_s: 600,
+            count: 1,
+            spread_s: 0,
+        };
+        assert!(burst.validate(600).is_err());
+
+        let curve = ArrivalProcess::TimeOfDay {
+            rates_per_min: vec![1.0; 23],
+        };
+        assert!(curve.validate(600).is_err());
+    }
+
+    #[test]
+    fn arrivals_are_sorted_capped_and_within_the_simulation() {
+        let processes = [
+            ArrivalProcess::Poisson { rate_per_min: 60.0 },
+            ArrivalProcess::Burst {
+                at_s: 100,
+                count: 20,
+                spread_s: 10,
+            },
+        ];
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let times = arrival_times(&processes, start(), 600, 10_000, &mut rng);
+        assert!(times.windows(2).all(|w| w[0] <= w[1]));
+        assert!(times.iter().all(|t| *t >= start() && *t < start() + Duration::seconds(600)));
+
+        // About 600 from the Poisson process plus the burst
+        assert!((470..=770).contains(&times.len()), "got {}", times.len());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let capped = arrival_times(&processes, start(), 600, 50, &mut rng);
+        assert_eq!(capped, times[..50]);
+    }
+
+    #[test]
+    fn burst_without_spread_arrives_at_once() {
+        let burst = ArrivalProcess::Burst {
+            at_s: 30,
+            count: 5,
+            spread_s: 0,
+        };
+
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(burst.sample(start(), 60, &mut rng), vec![30.0; 5]);
+    }
+
+    #[test]
+    fn ramp_only_produces_arrivals_while_ramping() {
+        let ramp = ArrivalProcess::Ramp {
+            from_per_min: 60.0,
+            to_per_min: 60.0,
+            start_s: 100,
+            end_s: Some(200),
+        };
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let offsets = ramp.sample(start(), 600, &mut rng);
+        assert!(!offsets.is_empty());
+        assert!(offsets.iter().all(|t| (100.0..200.0).contains(t)));
+    }
+
+    #[test]
+    fn time_of_day_follows_the_hour() {
+        let mut rates_per_min = vec![0.0; 24];
+        rates_per_min[1] = 30.0;
+        let curve = ArrivalProcess::TimeOfDay { rates_per_min };
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let offsets = curve.sample(start(), 7200, &mut rng);
+        assert!(!offsets.is_empty());
+        assert!(offsets.iter().all(|t| (3600.0..7200.0).contains(t)));
+    }
+}
@@ -1,9 +1,12 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use hyper::{StatusCode, body};
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+use uuid::{Builder, Uuid};
 
 use svc_cargo_client_rest::types::{
     VertiportsQuery,
@@ -14,17 +17,22 @@ use svc_cargo_client_rest::types::{
     FlightQuery
 };
 
-mod customer_events {
-    include!("../events/customer_events.rs");
-}
-
-use customer_events::{
+use crate::event_types::customer_events::{
     CustomerEvent,
-    CargoRequest
+    CargoRequest,
+    FlightModify,
+    PassengerRequest,
+    PassengerQuery
 };
+use crate::event_types::Dispatcher;
+use crate::eel_types::EelEventType;
+use crate::cfg_types::Delay;
+
+/// Weight of the cargo customers ask to send (kilograms)
+const CARGO_WEIGHT_KG: f32 = 1.0;
 
 /// Phases of customer activity
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
 pub enum CustomerStatus {
     /// Retrieving list of local vertiports
     Vertiports,
@@ -35,33 +43,186 @@ pub enum CustomerStatus {
     /// Has a list of flight options to select
     Confirm,
 
+    /// Changing the confirmed flight
+    Modify,
+
     /// Debating on cancelling
     Cancel,
 
+    /// Holding a confirmed flight until it departs
+    Booked,
+
     /// No more actions to be taken
-    Done
+    Done,
+
+    /// Gave up waiting before confirming a flight
+    Abandoned
+}
+
+/// Timings and milestones of a single customer
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct CustomerStatistics {
+    /// Simulation time at which the customer was created
+    pub spawn_time: Option<chrono::NaiveDateTime>,
+
+    /// Total time spent retrieving vertiports (milliseconds)
+    pub vertiports_wait_ms: f64,
+
+    /// Total time spent querying for flights (milliseconds)
+    pub query_wait_ms: f64,
+
+    /// Total time spent confirming a flight (milliseconds)
+    pub confirm_wait_ms: f64,
+
+    /// Total time spent modifying a flight (milliseconds)
+    pub modify_wait_ms: f64,
+
+    /// Total time spent cancelling a flight (milliseconds)
+    pub cancel_wait_ms: f64,
+
+    /// Number of actions that failed and had to be retried
+    pub retries: u8,
+
+    /// Received a list of vertiports
+    pub got_vertiports: bool,
+
+    /// Received a list of flight options
+    pub got_options: bool,
+
+    /// Confirmed a flight plan
+    pub confirmed: bool,
+
+    /// Changed a confirmed flight plan
+    pub modified: bool,
+
+    /// Tried and failed to change a confirmed flight plan
+    pub modify_failed: bool,
+
+    /// Cancelled a confirmed flight plan
+    pub cancelled: bool,
+
+    /// Lost a confirmed flight to a closure, restriction or denial
+    pub disrupted: bool,
+}
+
+impl CustomerStatistics {
+    /// Adds time spent acting in a phase
+    fn add_wait(&mut self, status: CustomerStatus, wait: Duration) {
+        let wait_ms = wait.as_secs_f64() * 1000.0;
+        match status {
+            CustomerStatus::Vertiports => self.vertiports_wait_ms += wait_ms,
+            CustomerStatus::Query => self.query_wait_ms += wait_ms,
+            CustomerStatus::Confirm => self.confirm_wait_ms += wait_ms,
+            CustomerStatus::Modify => self.modify_wait_ms += wait_ms,
+            CustomerStatus::Cancel => self.cancel_wait_ms += wait_ms,
+            _ => {}
+        }
+    }
+}
+
+/// Where a customer ended up at the end of a simulation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomerOutcome {
+    /// Customer ID
+    pub id: String,
+
+    /// The name of the customer's behavior
+    pub customer_type: String,
+
+    /// The customer's final phase
+    pub status: CustomerStatus,
+
+    /// The confirmed flight plan, if any
+    pub fp_id: Option<String>,
+
+    /// How long each phase took and how far the customer got
+    pub statistics: CustomerStatistics,
 }
 
-// #[derive(Default)]
-// pub struct CustomerStatistics {
-//     spawn_time: Option<SystemTime>,
-//     query_wait: Option<Duration>,
-//     confirm_wait: Option<Duration>,
-//     cancel_wait: Option<Duration>
-// }
+/// How many customers of one behavior reached each step of a booking
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct Funnel {
+    /// Customers created
+    pub spawned: usize,
+
+    /// Customers that received a list of vertiports
+    pub got_vertiports: usize,
+
+    /// Customers that received flight options
+    pub got_options: usize,
+
+    /// Customers that confirmed a flight plan
+    pub confirmed: usize,
+
+    /// Customers that changed their flight plan
+    pub modified: usize,
+
+    /// Customers that cancelled their flight plan
+    pub cancelled: usize,
+
+    /// Customers that kept their flight plan
+    pub kept: usize,
+
+    /// Customers that lost their flight plan to the authorities
+    pub disrupted: usize,
+}
+
+impl Funnel {
+    /// Adds a customer to the funnel
+    pub fn add(&mut self, stats: &CustomerStatistics) {
+        self.spawned += 1;
+        self.got_vertiports += stats.got_vertiports as usize;
+        self.got_options += stats.got_options as usize;
+        self.confirmed += stats.confirmed as usize;
+        self.modified += stats.modified as usize;
+        self.cancelled += stats.cancelled as usize;
+        self.kept += (stats.confirmed && !stats.cancelled && !stats.disrupted) as usize;
+        self.disrupted += stats.disrupted as usize;
+    }
+
+    /// Builds a funnel for each behavior
+    /// # Arguments
+    /// * outcomes - Where each customer ended up
+    pub fn by_customer_type(outcomes: &[CustomerOutcome]) -> BTreeMap<String, Funnel> {
+        let mut funnels: BTreeMap<String, Funnel> = BTreeMap::new();
+        for o in outcomes {
+            funnels
+                .entry(o.customer_type.clone())
+                .or_default()
+                .add(&o.statistics);
+        }
+
+        funnels
+    }
+}
 
 /// Customer Details
 #[allow(missing_debug_implementations)]
 pub struct Customer {
     id: Uuid,
-    behavior: Box<dyn CustomerBehavior>,
+    customer_type: String,
+    behavior: Box<dyn CustomerBehavior + Send>,
     status: CustomerStatus,
     vertiport_depart_id: String,
     vertiport_arrive_id: String,
+    route: Vec<Vertiport>,
+    departure: Option<chrono::NaiveDateTime>,
+    must_cancel: bool,
+    weather_cancel: bool,
+    weather_considered: bool,
     current_time: SystemTime,
+    sim_time: chrono::NaiveDateTime,
+    next_action_time: chrono::NaiveDateTime,
+    arrival_window: (SystemTime, SystemTime),
     fp_id: String,
     flights: Vec<FlightOption>,
-    retries: i8
+    options_received_at: SystemTime,
+    waited: Duration,
+    last_step_time: chrono::NaiveDateTime,
+    retries: u8,
+    stats: CustomerStatistics,
+    arrived: bool,
+    rng: StdRng
 }
 
 /// How customers exhibit behaviors
@@ -69,107 +230,504 @@ trait CustomerBehavior {
     /// How a customer responds to a list of available flights
     /// # Arguments
     /// * `flights` - A list of draft flight plans to choose from
+    /// * `window` - The earliest and latest arrival times the customer asked for
+    /// * `rng` - The customer's random number generator
     ///
     /// # Returns
     /// A flight plan ID from the list
-    fn confirm(&self, flights: &[FlightOption]) -> Option<String>;
+    fn confirm(
+        &self,
+        flights: &[FlightOption],
+        window: (SystemTime, SystemTime),
+        rng: &mut StdRng
+    ) -> Option<String>;
+
+    /// Longest total simulated time the customer will spend looking
+    ///  for vertiports and flights before giving up (forever if `None`)
+    fn patience(&self) -> Option<Duration>;
+
+    /// How long a draft flight plan is expected to stay valid
+    fn draft_ttl(&self) -> Duration;
 
-    /// Willingness to wait N seconds
-    // fn patience(&self, seconds: Duration) -> bool;
+    /// Probability of waiting until the draft has expired before confirming
+    fn stale_confirm_chance(&self) -> f32;
 
-    /// Delay Range
-    // fn delay_seconds(&self,) -> (u16, u16);
+    /// How long the customer takes before acting in a new phase
+    /// # Arguments
+    /// * `status` - The phase the customer just entered
+    /// * `rng` - The customer's random number generator
+    fn think_time(&self, status: CustomerStatus, rng: &mut StdRng) -> chrono::Duration;
 
     /// Probability of cancelling a confirmed flight
     fn cancel_chance(&self) -> f32;
+
+    /// Probability of changing a confirmed flight
+    fn modify_chance(&self) -> f32;
+
+    /// Probability of giving up on or cancelling a flight
+    ///  when the weather is adverse
+    fn weather_cancel_chance(&self) -> f32;
+
+    /// Number of failed requests tolerated before giving up
+    fn retries(&self) -> u8;
+
+    /// The service the customer books flights through
+    fn service(&self) -> Service;
+
+    /// Number of people flying (rideshare and charter only)
+    fn passengers(&self) -> u32;
 }
 
-/// Greedy customers take the first thing they can get and don't cancel
-#[derive(Debug, Clone, Copy)]
-pub struct GreedyCustomer;
+/// The customer-facing service a customer books flights through
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Service {
+    /// svc-cargo
+    #[default]
+    Cargo,
+
+    /// svc-rideshare
+    Rideshare,
+
+    /// svc-charter
+    Charter,
+}
 
-impl CustomerBehavior for GreedyCustomer {
-    fn confirm(&self, flights: &[FlightOption]) -> Option<String> {
-        // Just confirm the first available flight, immediately
-        flights.get(0).map(|f| f.fp_id.clone())
+impl Service {
+    /// Sends a request to this service, as either a cargo request or a
+    ///  rideshare or charter request
+    fn request(self, cargo: CargoRequest, passenger: PassengerRequest) -> CustomerEvent {
+        match self {
+            Service::Cargo => CustomerEvent::CargoRequest(cargo),
+            Service::Rideshare => CustomerEvent::Rideshare(passenger),
+            Service::Charter => CustomerEvent::Charter(passenger),
+        }
     }
 
-    fn cancel_chance(&self) -> f32 {
-        0.0
+    /// A request for the list of vertiports
+    fn vertiports(self, query: VertiportsQuery) -> CustomerEvent {
+        self.request(
+            CargoRequest::Vertiports(query.clone()),
+            PassengerRequest::Vertiports(query),
+        )
+    }
+
+    /// A request for flights between two vertiports
+    /// # Arguments
+    /// * depart - The departure vertiport's ID
+    /// * arrive - The arrival vertiport's ID
+    /// * window - Earliest and latest acceptable arrival
+    /// * passengers - Number of people flying, for rideshare and charter
+    fn query(
+        self,
+        depart: String,
+        arrive: String,
+        window: (SystemTime, SystemTime),
+        passengers: u32,
+    ) -> CustomerEvent {
+        let passenger = PassengerQuery {
+            vertiport_depart_id: depart.clone(),
+            vertiport_arrive_id: arrive.clone(),
+            timestamp_depart_min: None,
+            timestamp_depart_max: None,
+            timestamp_arrive_min: Some(window.0),
+            timestamp_arrive_max: Some(window.1),
+            passengers,
+            cargo_weight_kg: None,
+        };
+
+        let cargo = FlightQuery {
+            vertiport_depart_id: depart,
+            vertiport_arrive_id: arrive,
+            timestamp_depart_min: None,
+            timestamp_depart_max: None,
+            timestamp_arrive_min: Some(window.0),
+            timestamp_arrive_max: Some(window.1),
+            cargo_weight_kg: CARGO_WEIGHT_KG,
+        };
+
+        self.request(CargoRequest::Query(cargo), PassengerRequest::Query(passenger))
+    }
+
+    /// A request to confirm a draft flight plan
+    fn confirm(self, confirm: FlightConfirm) -> CustomerEvent {
+        self.request(
+            CargoRequest::Confirm(confirm.clone()),
+            PassengerRequest::Confirm(confirm),
+        )
+    }
+
+    /// A request to cancel a confirmed flight plan
+    fn cancel(self, cancel: FlightCancel) -> CustomerEvent {
+        self.request(
+            CargoRequest::Cancel(cancel.clone()),
+            PassengerRequest::Cancel(cancel),
+        )
     }
 }
 
-/// Mistake customers create a booking on accident and need to cancel
-#[derive(Debug, Clone, Copy)]
-pub struct MistakeCustomer;
+/// How a customer picks from a list of flight options
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// Take the first option offered
+    First,
+
+    /// Never pick anything
+    None,
+
+    /// Take the lowest priced option
+    Cheapest,
+
+    /// Take the option that arrives soonest
+    EarliestArrival,
+
+    /// Take the option that departs last while still arriving in time
+    LatestDeparture,
+
+    /// Take any option at random
+    Random,
+
+    /// Take the first option priced at or under `max_price_usd`
+    PriceThreshold,
+}
+
+/// Time a customer takes before acting in each phase
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThinkTimes {
+    /// Before querying for flights, after picking vertiports
+    pub query: Delay,
+
+    /// Before confirming, after receiving flight options
+    pub confirm: Delay,
 
-impl CustomerBehavior for MistakeCustomer {
-    fn confirm(&self, flights: &[FlightOption]) -> Option<String> {
-        // Just confirm the first available flight, immediately
-        flights.get(0).map(|f| f.fp_id.clone())
+    /// Before modifying, after confirming
+    pub modify: Delay,
+
+    /// Before deciding whether to cancel, after confirming
+    pub cancel: Delay,
+}
+
+impl ThinkTimes {
+    /// Checks that none of the delays can be negative
+    pub fn validate(&self) -> Result<(), String> {
+        self.query.validate()?;
+        self.confirm.validate()?;
+        self.modify.validate()?;
+        self.cancel.validate()
     }
+}
 
-    fn cancel_chance(&self) -> f32 {
-        // Will cancel
-        1.0
+/// A customer persona, declared in the configuration file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct BehaviorProfile {
+    /// The service the customer books flights through
+    pub service: Service,
+
+    /// Number of people flying (rideshare and charter only)
+    pub passengers: u32,
+
+    /// How the customer picks a flight from the options offered
+    pub selection: Selection,
+
+    /// Highest price the customer will pay (`price_threshold` selection)
+    pub max_price_usd: Option<f32>,
+
+    /// Probability of cancelling a confirmed flight (0.0 to 1.0)
+    pub cancel_chance: f32,
+
+    /// Probability of changing the weight or arrival window of a
+    ///  confirmed flight (0.0 to 1.0, cargo only)
+    pub modify_chance: f32,
+
+    /// Number of failed requests tolerated before giving up
+    pub retries: u8,
+
+    /// Time taken before acting in each phase (no delay if not provided)
+    pub think_time: ThinkTimes,
+
+    /// Longest total simulated time (seconds) the customer will spend
+    ///  looking for vertiports and flights before abandoning (forever if
+    ///  not provided)
+    pub patience_s: Option<f64>,
+
+    /// How long (seconds) svc-cargo is expected to keep draft flight plans
+    pub draft_ttl_s: f64,
+
+    /// Probability of waiting until the draft has expired before
+    ///  confirming it (0.0 to 1.0)
+    pub stale_confirm_chance: f32,
+
+    /// Probability of giving up on booking, or cancelling a booked
+    ///  flight, when the weather at either vertiport is adverse (0.0 to 1.0)
+    pub weather_cancel_chance: f32,
+}
+
+impl Default for BehaviorProfile {
+    fn default() -> Self {
+        BehaviorProfile {
+            service: Service::Cargo,
+            passengers: 1,
+            selection: Selection::First,
+            max_price_usd: None,
+            cancel_chance: 0.0,
+            modify_chance: 0.0,
+            retries: 1,
+            think_time: ThinkTimes::default(),
+            patience_s: None,
+            draft_ttl_s: 30.0,
+            stale_confirm_chance: 0.0,
+            weather_cancel_chance: 0.25,
+        }
     }
 }
 
-/// Indecisive customers query but never select anything
-#[derive(Debug, Clone, Copy)]
-pub struct IndecisiveCustomer;
+impl BehaviorProfile {
+    /// Behaviors available without being declared in the configuration
+    /// # Arguments
+    /// * name - The name of the behavior
+    pub fn builtin(name: &str) -> Option<Self> {
+        let default = BehaviorProfile::default();
+        match name {
+            // Take the first thing they can get and don't cancel unless
+            //  the weather turns
+            "greedy" => Some(default),
+
+            // Create a booking on accident and need to cancel
+            "mistake" => Some(BehaviorProfile {
+                cancel_chance: 1.0,
+                ..default
+            }),
+
+            // Query but never select anything
+            "indecisive" => Some(BehaviorProfile {
+                selection: Selection::None,
+                cancel_chance: 1.0,
+                ..default
+            }),
 
-impl CustomerBehavior for IndecisiveCustomer {
-    fn confirm(&self, _flights: &[FlightOption]) -> Option<String> {
-        None
+            _ => None,
+        }
+    }
+
+    /// Checks that the profile's values are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.cancel_chance) {
+            return Err(format!(
+                "cancel_chance must be between 0 and 1, got {}",
+                self.cancel_chance
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.modify_chance) {
+            return Err(format!(
+                "modify_chance must be between 0 and 1, got {}",
+                self.modify_chance
+            ));
+        }
+
+        if self.modify_chance > 0.0 && self.service != Service::Cargo {
+            return Err("modify_chance only applies to the cargo service".to_string());
+        }
+
+        if self.passengers == 0 {
+            return Err("passengers must be at least 1".to_string());
+        }
+
+        if self.retries == 0 {
+            return Err("retries must be at least 1".to_string());
+        }
+
+        if !(0.0..=1.0).contains(&self.stale_confirm_chance) {
+            return Err(format!(
+                "stale_confirm_chance must be between 0 and 1, got {}",
+                self.stale_confirm_chance
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.weather_cancel_chance) {
+            return Err(format!(
+                "weather_cancel_chance must be between 0 and 1, got {}",
+                self.weather_cancel_chance
+            ));
+        }
+
+        if matches!(self.patience_s, Some(p) if p < 0.0 || !p.is_finite()) {
+            return Err("patience_s must be zero or positive".to_string());
+        }
+
+        if self.draft_ttl_s < 0.0 || !self.draft_ttl_s.is_finite() {
+            return Err("draft_ttl_s must be zero or positive".to_string());
+        }
+
+        if self.selection == Selection::PriceThreshold && self.max_price_usd.is_none() {
+            return Err("price_threshold selection needs max_price_usd".to_string());
+        }
+
+        self.think_time.validate()
+    }
+}
+
+/// Orders prices low to high, with unpriced options last
+fn price_key(flight: &FlightOption) -> f32 {
+    flight.base_pricing.unwrap_or(f32::INFINITY)
+}
+
+impl CustomerBehavior for BehaviorProfile {
+    fn confirm(
+        &self,
+        flights: &[FlightOption],
+        window: (SystemTime, SystemTime),
+        rng: &mut StdRng
+    ) -> Option<String> {
+        let choice = match self.selection {
+            Selection::First => flights.first(),
+            Selection::None => None,
+            Selection::Cheapest => flights
+                .iter()
+                .min_by(|a, b| price_key(a).total_cmp(&price_key(b))),
+            Selection::EarliestArrival => flights.iter().min_by_key(|f| f.timestamp_arrive),
+            Selection::LatestDeparture => flights
+                .iter()
+                .filter(|f| f.timestamp_arrive >= window.0 && f.timestamp_arrive <= window.1)
+                .max_by_key(|f| f.timestamp_depart),
+            Selection::Random => flights.choose(rng),
+            Selection::PriceThreshold => {
+                let max_price = self.max_price_usd.unwrap_or(f32::INFINITY);
+                flights
+                    .iter()
+                    .find(|f| matches!(f.base_pricing, Some(p) if p <= max_price))
+            }
+        };
+
+        choice.map(|f| f.fp_id.clone())
     }
 
     fn cancel_chance(&self) -> f32 {
-        // Will cancel
-        1.0
+        self.cancel_chance
+    }
+
+    fn modify_chance(&self) -> f32 {
+        self.modify_chance
+    }
+
+    fn weather_cancel_chance(&self) -> f32 {
+        self.weather_cancel_chance
+    }
+
+    fn retries(&self) -> u8 {
+        self.retries
+    }
+
+    fn service(&self) -> Service {
+        self.service
+    }
+
+    fn passengers(&self) -> u32 {
+        self.passengers
+    }
+
+    fn patience(&self) -> Option<Duration> {
+        self.patience_s.map(Duration::from_secs_f64)
+    }
+
+    fn draft_ttl(&self) -> Duration {
+        Duration::from_secs_f64(self.draft_ttl_s)
+    }
+
+    fn stale_confirm_chance(&self) -> f32 {
+        self.stale_confirm_chance
+    }
+
+    fn think_time(&self, status: CustomerStatus, rng: &mut StdRng) -> chrono::Duration {
+        let delay = match status {
+            CustomerStatus::Query => self.think_time.query,
+            CustomerStatus::Confirm => self.think_time.confirm,
+            CustomerStatus::Modify => self.think_time.modify,
+            CustomerStatus::Cancel => self.think_time.cancel,
+            _ => return chrono::Duration::zero(),
+        };
+
+        delay.sample(rng)
     }
 }
 
+/// Converts a simulation timestamp into the format used by svc-cargo
+fn to_system_time(time: chrono::NaiveDateTime) -> SystemTime {
+    SystemTime::try_from(
+        prost_types::Timestamp {
+            seconds: time.timestamp(),
+            nanos: time.timestamp_subsec_nanos() as i32
+        }
+    ).unwrap()
+}
+
+/// Reads a response body as text
+async fn read_text(resp: hyper::Response<hyper::Body>) -> Result<String, String> {
+    let bytes = body::to_bytes(resp.into_body()).await.map_err(|e| e.to_string())?;
+    String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+}
+
+/// Reads a response body as JSON
+async fn read_json<T: serde::de::DeserializeOwned>(
+    resp: hyper::Response<hyper::Body>
+) -> Result<T, String> {
+    let bytes = body::to_bytes(resp.into_body()).await.map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
 impl Customer {
     /// Creates a customer, assigns it a behavior and desired itinerary details
     /// # Arguments
-    /// * customer_type - A string
-    /// * vertiport_depart_id - The UUID of the departure vertiport
-    /// * vertiport_arrive_id - The UUID of the arrival vertiport
+    /// * customer_type - The name of the customer's behavior
+    /// * behavior - How the customer behaves
+    /// * current_time - The simulation time at which the customer is created
+    /// * rng - The simulation's seeded RNG, used to seed the customer's own RNG
     pub fn generate(
         customer_type: &str,
-        current_time: chrono::NaiveDateTime
+        behavior: BehaviorProfile,
+        current_time: chrono::NaiveDateTime,
+        rng: &mut StdRng
     ) -> Self {
-        let uuid = Uuid::new_v4();
+        // Each customer draws from its own RNG so that results
+        //  don't depend on the order in which customers are polled
+        let mut rng = StdRng::seed_from_u64(rng.gen());
+        let uuid = Builder::from_random_bytes(rng.gen()).into_uuid();
         println!("Creating '{}' customer {}", customer_type, uuid);
-        let customer: Box<dyn CustomerBehavior> = match customer_type {
-            "greedy" => Box::new(GreedyCustomer),
-            "mistake" => Box::new(MistakeCustomer),
-            "indecisive" => Box::new(IndecisiveCustomer),
-
-            _ => {
-                println!("Invalid customer type '{}'; producing greedy customer", customer_type);
-                Box::new(GreedyCustomer)
-            }
-        };
 
-        let time: SystemTime = SystemTime::try_from(
-            prost_types::Timestamp {
-                seconds: current_time.timestamp(),
-                nanos: current_time.timestamp_subsec_nanos() as i32
-            }
-        ).unwrap();
+        let time = to_system_time(current_time);
+        let retries = behavior.retries();
 
         Customer {
             id: uuid,
-            behavior: customer,
+            customer_type: customer_type.to_string(),
+            behavior: Box::new(behavior),
             status: CustomerStatus::Vertiports,
             vertiport_depart_id: "".to_string(),
             vertiport_arrive_id: "".to_string(),
+            route: vec!(),
+            departure: None,
+            must_cancel: false,
+            weather_cancel: false,
+            weather_considered: false,
             current_time: time,
+            sim_time: current_time,
+            next_action_time: current_time,
+            arrival_window: (time, time),
             fp_id: "".to_string(),
             flights: vec!(),
-            retries: 1
+            options_received_at: time,
+            waited: Duration::ZERO,
+            last_step_time: current_time,
+            retries,
+            stats: CustomerStatistics {
+                spawn_time: Some(current_time),
+                ..Default::default()
+            },
+            arrived: false,
+            rng
         }
     }
 
@@ -178,19 +736,17 @@ impl Customer {
         println!("{:?}: {}", self.id, s);
     }
 
-    async fn handle_vertiports(&mut self) -> bool {
-        let query = CustomerEvent::CargoRequest(
-            CargoRequest::Vertiports(
-                // Arbitrary, not currently used
-                VertiportsQuery {
-                    latitude: 100.,
-                    longitude: 100.
-                }
-            )
+    async fn handle_vertiports(&mut self, dispatcher: &Dispatcher) -> bool {
+        let query = self.behavior.service().vertiports(
+            // Arbitrary, not currently used
+            VertiportsQuery {
+                latitude: 100.,
+                longitude: 100.
+            }
         );
 
         self.log("Attempting to query for vertiports...");
-        let act = customer_events::action(&query).await;
+        let act = dispatcher.dispatch(&EelEventType::CustomerEvent(query), self.sim_time).await;
         if let Err(e) = act {
             self.log(&format!("Failed to get vertiports: {:?}", e));
             return false;
@@ -202,23 +758,49 @@ impl Customer {
             return false;
         }
 
-        let bytes = body::to_bytes(resp.into_body()).await.unwrap();
-        let mut vertiports: Vec<Vertiport> = serde_json::from_slice(&bytes).unwrap();
+        let mut vertiports: Vec<Vertiport> = match read_json(resp).await {
+            Ok(vertiports) => vertiports,
+            Err(e) => {
+                self.log(&format!("Unexpected vertiports response: {}", e));
+                return false;
+            }
+        };
+
         if vertiports.len() < 2 {
             self.log(&format!("Not enough vertiports available: {}.", vertiports.len()));
             return false;
         }
 
+        // Steer clear of closed and restricted vertiports
+        vertiports.retain(|v| {
+            dispatcher
+                .world
+                .restriction(&v.id, v.latitude, v.longitude, self.sim_time)
+                .is_none()
+        });
+
+        // The service is fine, there's just nowhere to fly
+        if vertiports.len() < 2 {
+            self.log(&format!(
+                "Only {} vertiports are unrestricted, abandoning.",
+                vertiports.len()
+            ));
+            self.status = CustomerStatus::Abandoned;
+            return true;
+        }
+
         self.log(&format!("Received {} vertiports.", vertiports.len()));
 
-        vertiports = vertiports.choose_multiple(&mut rand::thread_rng(), 2).cloned().collect();
+        vertiports = vertiports.choose_multiple(&mut self.rng, 2).cloned().collect();
         self.vertiport_depart_id = vertiports[0].id.clone();
         self.vertiport_arrive_id = vertiports[1].id.clone();
+        self.route = vertiports;
         self.log(&format!(
             "Picking two arbitrary vertiports: {} and {}",
             self.vertiport_depart_id,
             self.vertiport_arrive_id
         ));
+        self.stats.got_vertiports = true;
         self.status = CustomerStatus::Query;
         true
     }
@@ -226,23 +808,25 @@ impl Customer {
     /// Queries for available routes
     /// # Returns
     /// true if successful
-    async fn handle_query(&mut self) -> bool {
-        let query = CustomerEvent::CargoRequest(
-            CargoRequest::Query(
-                FlightQuery {
-                    vertiport_depart_id: self.vertiport_depart_id.clone(),
-                    vertiport_arrive_id: self.vertiport_arrive_id.clone(),
-                    timestamp_depart_min: None,
-                    timestamp_depart_max: None,
-                    timestamp_arrive_min: Some(self.current_time + Duration::from_secs(60)),
-                    timestamp_arrive_max: Some(self.current_time + Duration::from_secs(600)),
-                    cargo_weight_kg: 1.0,
-                }
-            )
+    async fn handle_query(&mut self, dispatcher: &Dispatcher) -> bool {
+        if self.reroute(dispatcher) {
+            return true;
+        }
+
+        self.arrival_window = (
+            self.current_time + Duration::from_secs(60),
+            self.current_time + Duration::from_secs(600)
+        );
+
+        let query = self.behavior.service().query(
+            self.vertiport_depart_id.clone(),
+            self.vertiport_arrive_id.clone(),
+            self.arrival_window,
+            self.behavior.passengers(),
         );
 
         self.log("Attempting to query for flight...");
-        let act = customer_events::action(&query).await;
+        let act = dispatcher.dispatch(&EelEventType::CustomerEvent(query), self.sim_time).await;
         if let Err(e) = act {
             self.log(&format!("Failed to query: {:?}", e));
             return false;
@@ -254,18 +838,34 @@ impl Customer {
             return false;
         }
 
-        let bytes = body::to_bytes(resp.into_body()).await.unwrap();
-        self.flights = serde_json::from_slice(&bytes).unwrap();
+        // svc-rideshare and svc-charter are assumed to answer with the same
+        //  flight options as svc-cargo, anything else fails the query
+        self.flights = match read_json(resp).await {
+            Ok(flights) => flights,
+            Err(e) => {
+                self.log(&format!("Unexpected flight options: {}", e));
+                return false;
+            }
+        };
+
+        // The services don't know about aircraft grounded by the environment
+        if !dispatcher.world.has_aircraft_at(&self.vertiport_depart_id, self.sim_time) {
+            self.log(&format!("No aircraft available at {}.", self.vertiport_depart_id));
+            self.flights.clear();
+        }
+
         if self.flights.is_empty() {
             self.log("No routes available.");
             return false;
         }
 
         for f in &self.flights {
-            self.log(&format!("Option: {} ({:?} USD)", f.fp_id, f.base_pricing.unwrap()));
+            self.log(&format!("Option: {} ({:?} USD)", f.fp_id, f.base_pricing));
         }
 
         self.log(&format!("Received {} flight options.", self.flights.len()));
+        self.options_received_at = self.current_time;
+        self.stats.got_options = true;
         self.status = CustomerStatus::Confirm;
         true
     }
@@ -273,8 +873,20 @@ impl Customer {
     /// Confirms the flight plan that the customer selected
     /// # Returns
     /// true if successful
-    async fn handle_confirm(&mut self) -> bool {
-        let ret = self.behavior.confirm(&self.flights);
+    async fn handle_confirm(&mut self, dispatcher: &Dispatcher) -> bool {
+        if self.reroute(dispatcher) {
+            return true;
+        }
+
+        if let Some(id) = self.adverse_weather(dispatcher) {
+            if self.rng.gen_bool(self.behavior.weather_cancel_chance().into()) {
+                self.log(&format!("Bad weather at {}, giving up on booking.", id));
+                self.status = CustomerStatus::Abandoned;
+                return true;
+            }
+        }
+
+        let ret = self.behavior.confirm(&self.flights, self.arrival_window, &mut self.rng);
         if ret.is_none() {
             self.log("Did not select a flight.");
             return false;
@@ -282,15 +894,21 @@ impl Customer {
 
         let draft_fp_id = ret.unwrap();
         
-        let confirm_query = CustomerEvent::CargoRequest(
-            CargoRequest::Confirm(
-                FlightConfirm {
-                    fp_id: draft_fp_id.clone()
-                }
-        ));
+        let confirm_query = self.behavior.service().confirm(
+            FlightConfirm {
+                fp_id: draft_fp_id.clone()
+            }
+        );
+
+        // Drafts are only held for so long
+        let draft_age = self
+            .current_time
+            .duration_since(self.options_received_at)
+            .unwrap_or(Duration::ZERO);
+        let likely_expired = draft_age > self.behavior.draft_ttl();
 
         self.log(&format!("Confirming draft ID {}...", &draft_fp_id));
-        let act = customer_events::action(&confirm_query).await;
+        let act = dispatcher.dispatch(&EelEventType::CustomerEvent(confirm_query), self.sim_time).await;
         if act.is_err() {
             self.log("Failed to confirm.");
             return false;
@@ -299,35 +917,130 @@ impl Customer {
         let resp = act.unwrap();
         if resp.status() != StatusCode::OK {
             self.log(&format!("Bad Response: {}", resp.status()));
-            return false;
+            return self.confirm_rejected(draft_age);
+        }
+
+        if likely_expired {
+            self.log(&format!(
+                "Confirmed a draft that should have expired ({}s old).",
+                draft_age.as_secs()
+            ));
         }
 
-        let bytes = body::to_bytes(resp.into_body()).await.unwrap();
-        let fp_id = String::from_utf8(bytes.to_vec()).unwrap();
+        let fp_id = match read_text(resp).await {
+            Ok(fp_id) => fp_id,
+            Err(e) => {
+                self.log(&format!("Unexpected confirmation: {}", e));
+                return false;
+            }
+        };
         self.log(&format!("Confirmed, assigned plan {}.", fp_id));
         self.fp_id = fp_id;
+        self.departure = self
+            .flights
+            .iter()
+            .find(|f| f.fp_id == draft_fp_id)
+            .map(|f| chrono::DateTime::<chrono::Utc>::from(f.timestamp_depart).naive_utc());
+        self.stats.confirmed = true;
+        self.status = if self.rng.gen_bool(self.behavior.modify_chance().into()) {
+            CustomerStatus::Modify
+        } else {
+            CustomerStatus::Cancel
+        };
+        true
+    }
+
+    /// Changes the weight or arrival window of the confirmed flight
+    ///
+    /// A refused change keeps the confirmed flight, so the customer
+    ///  moves on to deciding whether to cancel it either way.
+    /// # Returns
+    /// true, a failed change isn't retried
+    async fn handle_modify(&mut self, dispatcher: &Dispatcher) -> bool {
+        let mut modify = FlightModify {
+            fp_id: self.fp_id.clone(),
+            cargo_weight_kg: None,
+            timestamp_depart_min: None,
+            timestamp_depart_max: None,
+            timestamp_arrive_min: None,
+            timestamp_arrive_max: None,
+        };
+
+        if self.rng.gen_bool(0.5) {
+            let weight = CARGO_WEIGHT_KG * self.rng.gen_range(0.5..=2.0);
+            self.log(&format!("Changing cargo weight to {:.2} kg...", weight));
+            modify.cargo_weight_kg = Some(weight);
+        } else {
+            let shift = Duration::from_secs(self.rng.gen_range(300..=1800));
+            self.log(&format!("Pushing arrival back by {}s...", shift.as_secs()));
+            modify.timestamp_arrive_min = Some(self.arrival_window.0 + shift);
+            modify.timestamp_arrive_max = Some(self.arrival_window.1 + shift);
+        }
+
+        let query = CustomerEvent::CargoRequest(CargoRequest::Modify(modify.clone()));
+        let act = dispatcher.dispatch(&EelEventType::CustomerEvent(query), self.sim_time).await;
         self.status = CustomerStatus::Cancel;
+        let resp = match act {
+            Ok(resp) => resp,
+            Err(e) => {
+                self.log(&format!("Could not modify: {:?}", e));
+                self.stats.modify_failed = true;
+                return true;
+            }
+        };
+
+        if resp.status() != StatusCode::OK {
+            self.log(&format!("Bad Response: {}", resp.status()));
+            self.stats.modify_failed = true;
+            return true;
+        }
+
+        self.log(&format!("Modified plan {}.", self.fp_id));
+        if let (Some(min), Some(max)) = (modify.timestamp_arrive_min, modify.timestamp_arrive_max) {
+            // The flight leaves later by as much as the arrival moved
+            let shift = min
+                .duration_since(self.arrival_window.0)
+                .ok()
+                .and_then(|shift| chrono::Duration::from_std(shift).ok());
+            if let (Some(departure), Some(shift)) = (self.departure, shift) {
+                self.departure = Some(departure + shift);
+            }
+
+            self.arrival_window = (min, max);
+        }
+
+        self.stats.modified = true;
         true
     }
 
-    async fn handle_cancel(&mut self) -> bool {
+    async fn handle_cancel(&mut self, dispatcher: &Dispatcher) -> bool {
+        let mut cancel_chance = self.behavior.cancel_chance();
+        if self.must_cancel || self.weather_cancel {
+            cancel_chance = 1.0;
+        } else if let Some(id) = self.adverse_weather(dispatcher) {
+            self.log(&format!("Bad weather at {}, considering cancelling.", id));
+            self.weather_considered = true;
+            cancel_chance = cancel_chance.max(self.behavior.weather_cancel_chance());
+        }
+
         // To add: Actual probability distribution
-        let mut rng = rand::thread_rng();
-        if !rng.gen_bool(self.behavior.cancel_chance().into()) {
+        if !self.rng.gen_bool(cancel_chance.into()) {
             self.log("Chose not to cancel.");
-            self.status = CustomerStatus::Done;
+            self.status = match self.departure {
+                Some(d) if d > self.sim_time => CustomerStatus::Booked,
+                _ => CustomerStatus::Done,
+            };
             return true;
         }
     
-        let cancel_query = CustomerEvent::CargoRequest(
-            CargoRequest::Cancel(
-                FlightCancel {
-                    fp_id: self.fp_id.clone()
-                }
-        ));
+        let cancel_query = self.behavior.service().cancel(
+            FlightCancel {
+                fp_id: self.fp_id.clone()
+            }
+        );
 
         self.log(&format!("Cancelling plan {}", &self.fp_id));
-        let act = customer_events::action(&cancel_query).await;
+        let act = dispatcher.dispatch(&EelEventType::CustomerEvent(cancel_query), self.sim_time).await;
         if let Err(e) = act {
             self.log(&format!("Could not cancel: {:?}", e));
             return false;
@@ -343,36 +1056,535 @@ impl Customer {
 
         // let bytes = body::to_bytes(resp.into_body()).await.unwrap();
         // let id = String::from_utf8(bytes.to_vec()).unwrap();
+        self.stats.cancelled = true;
+        self.stats.disrupted = self.must_cancel;
         self.status = CustomerStatus::Done;
         true
     }
 
+    /// Queries again if the rejected draft had likely expired
+    ///
+    /// An expired draft is expected to be rejected, so it isn't
+    ///  counted against the customer's retries.
+    /// # Arguments
+    /// * draft_age - Time since the flight options were received
+    /// # Returns
+    /// true if the customer will query again
+    fn confirm_rejected(&mut self, draft_age: Duration) -> bool {
+        if draft_age <= self.behavior.draft_ttl() {
+            return false;
+        }
+
+        self.log(&format!(
+            "Draft was {}s old and has likely expired, querying again.",
+            draft_age.as_secs()
+        ));
+        self.status = CustomerStatus::Query;
+        true
+    }
+
+    /// Waits for the flight to depart, reacting to the authorities and
+    ///  the weather
+    /// # Returns
+    /// true if successful
+    fn handle_booked(&mut self, dispatcher: &Dispatcher) -> bool {
+        if let Some(d) = dispatcher.world.flight_plan_decision(&self.fp_id) {
+            if !d.approved {
+                self.log(&format!(
+                    "Flight plan {} was denied ({}).",
+                    self.fp_id,
+                    d.reason.as_deref().unwrap_or("no reason given")
+                ));
+                self.stats.disrupted = true;
+                self.status = CustomerStatus::Done;
+                return true;
+            }
+        }
+
+        if let Some(reason) = self.restriction(dispatcher) {
+            self.log(&format!("{}, cancelling plan {}.", reason, self.fp_id));
+            self.must_cancel = true;
+            self.status = CustomerStatus::Cancel;
+            return true;
+        }
+
+        // Weigh up cancelling once per spell of adverse weather
+        match self.adverse_weather(dispatcher) {
+            Some(id) if !self.weather_considered => {
+                self.weather_considered = true;
+                if self.rng.gen_bool(self.behavior.weather_cancel_chance().into()) {
+                    self.log(&format!("Bad weather at {}, cancelling plan {}.", id, self.fp_id));
+                    self.weather_cancel = true;
+                    self.status = CustomerStatus::Cancel;
+                    return true;
+                }
+
+                self.log(&format!("Bad weather at {}, keeping plan {}.", id, self.fp_id));
+            }
+            Some(_) => {}
+            None => self.weather_considered = false,
+        }
+
+        if matches!(self.departure, Some(d) if d <= self.sim_time) {
+            self.log(&format!("Flight {} departed.", self.fp_id));
+            self.status = CustomerStatus::Done;
+        }
+
+        true
+    }
+
+    /// Why the customer's route can't be flown (`None` if it can)
+    fn restriction(&self, dispatcher: &Dispatcher) -> Option<String> {
+        self.route.iter().find_map(|v| {
+            dispatcher
+                .world
+                .restriction(&v.id, v.latitude, v.longitude, self.sim_time)
+        })
+    }
+
+    /// Goes back to picking vertiports if the route can't be flown
+    /// # Returns
+    /// true if the customer has to pick new vertiports
+    fn reroute(&mut self, dispatcher: &Dispatcher) -> bool {
+        match self.restriction(dispatcher) {
+            Some(reason) => {
+                self.log(&format!("{}, picking new vertiports.", reason));
+                self.status = CustomerStatus::Vertiports;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The first vertiport on the customer's route with adverse weather
+    fn adverse_weather(&self, dispatcher: &Dispatcher) -> Option<String> {
+        self.route
+            .iter()
+            .find(|v| {
+                let weather =
+                    dispatcher.world.weather_at(&v.id, v.latitude, v.longitude, self.sim_time);
+                matches!(weather, Some(w) if w.is_adverse())
+            })
+            .map(|v| v.id.clone())
+    }
+
+    /// The name of the customer's behavior
+    pub fn customer_type(&self) -> &str {
+        &self.customer_type
+    }
+
+    /// A summary of where the customer ended up
+    pub fn outcome(&self) -> CustomerOutcome {
+        CustomerOutcome {
+            id: self.id.to_string(),
+            customer_type: self.customer_type.clone(),
+            status: self.status,
+            fp_id: if self.fp_id.is_empty() {
+                None
+            } else {
+                Some(self.fp_id.clone())
+            },
+            statistics: self.stats,
+        }
+    }
+
+    /// The simulation time at which the customer next wants to act
+    pub fn next_action_time(&self) -> chrono::NaiveDateTime {
+        self.next_action_time
+    }
+
+    /// The customer's unique ID
+    pub fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    /// The phase the customer is in
+    pub fn status(&self) -> CustomerStatus {
+        self.status
+    }
+
+    /// Whether the customer has no more actions to take
+    pub fn is_done(&self) -> bool {
+        matches!(self.status, CustomerStatus::Done | CustomerStatus::Abandoned)
+    }
+
     /// Prompts the customer to perform an action
-    pub async fn next(&mut self) {
+    /// # Arguments
+    /// * now - The current simulation time
+    /// * dispatcher - Sends requests and records the outcome
+    pub async fn next(&mut self, now: chrono::NaiveDateTime, dispatcher: &Dispatcher) {
+        self.current_time = to_system_time(now);
+        self.sim_time = now;
+        self.next_action_time = now;
+
+        if !self.arrived {
+            dispatcher.metrics.customer_moved(None, self.status);
+            self.arrived = true;
+        }
+
+        // Waiting on vertiports and flight options tests the customer's patience
+        let searching = matches!(
+            self.status,
+            CustomerStatus::Vertiports | CustomerStatus::Query
+        );
+        if searching {
+            self.waited += (now - self.last_step_time).to_std().unwrap_or(Duration::ZERO);
+        }
+        self.last_step_time = now;
+
+        let previous_status = self.status;
+        let started = Instant::now();
         let ret: bool = match self.status {
-            CustomerStatus::Done => {
+            CustomerStatus::Done | CustomerStatus::Abandoned => {
                 true
             },
             CustomerStatus::Vertiports => {
-                self.handle_vertiports().await
+                self.handle_vertiports(dispatcher).await
             },
             CustomerStatus::Query => {                
-                self.handle_query().await
+                self.handle_query(dispatcher).await
             },
             CustomerStatus::Confirm => {
-                self.handle_confirm().await
+                self.handle_confirm(dispatcher).await
+            },
+            CustomerStatus::Modify => {
+                self.handle_modify(dispatcher).await
             },
             CustomerStatus::Cancel => {
-                self.handle_cancel().await
+                self.handle_cancel(dispatcher).await
+            },
+            CustomerStatus::Booked => {
+                self.handle_booked(dispatcher)
             }
         };
+        self.stats.add_wait(previous_status, started.elapsed());
 
         if !ret {
+            if self.retries > 1 {
+                self.stats.retries += 1;
+            }
+
             self.retries -= 1;
-            if self.retries <= 0 {
+            if self.retries == 0 {
                 self.log("Customer reached max retries, dying.");
                 self.status = CustomerStatus::Done;
             }
         }
+
+        if searching && !self.is_done() {
+            if let Some(patience) = self.behavior.patience() {
+                if self.waited > patience {
+                    self.log(&format!(
+                        "Ran out of patience after waiting {:.1}s, abandoning.",
+                        self.waited.as_secs_f64()
+                    ));
+                    self.status = CustomerStatus::Abandoned;
+                }
+            }
+        }
+
+        // Take some time to think before acting in the new phase
+        if self.status != previous_status {
+            dispatcher.metrics.customer_moved(Some(previous_status), self.status);
+            let mut delay = self.behavior.think_time(self.status, &mut self.rng);
+
+            // Dawdle until the draft is stale
+            if self.status == CustomerStatus::Confirm
+                && self.rng.gen_bool(self.behavior.stale_confirm_chance().into())
+            {
+                let ttl = chrono::Duration::from_std(self.behavior.draft_ttl()).unwrap();
+                delay = delay.max(ttl + chrono::Duration::seconds(1));
+                self.log("Waiting for the draft to expire before confirming.");
+            }
+
+            self.next_action_time = now + delay;
+        }
+
+        // Nothing to do until the flight departs, unless the
+        //  authorities step in first
+        if self.status == CustomerStatus::Booked {
+            if let Some(d) = self.departure {
+                self.next_action_time = d;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, stub_service};
+
+    /// Two draft flight plans, as svc-cargo returns them
+    const FLIGHTS: &str = r#"[
+        {
+            "fp_id": "draft-1",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995500, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640995800, "nanos_since_epoch": 0},
+            "base_pricing": 50.0,
+            "currency_type": "usd"
+        },
+        {
+            "fp_id": "draft-2",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995560, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640995860, "nanos_since_epoch": 0},
+            "base_pricing": 60.0,
+            "currency_type": "usd"
+        }
+    ]"#;
+
+    /// Flight options that each selection strategy tells apart, for a
+    ///  query at the start that asks to arrive within 60s to 600s
+    const OPTIONS: &str = r#"[
+        {
+            "fp_id": "draft-a",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995440, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640995780, "nanos_since_epoch": 0},
+            "base_pricing": 50.0,
+            "currency_type": "usd"
+        },
+        {
+            "fp_id": "draft-b",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995500, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640995760, "nanos_since_epoch": 0},
+            "base_pricing": null,
+            "currency_type": "usd"
+        },
+        {
+            "fp_id": "draft-c",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995600, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640995800, "nanos_since_epoch": 0},
+            "base_pricing": 45.0,
+            "currency_type": "usd"
+        },
+        {
+            "fp_id": "draft-e",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640995700, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640996000, "nanos_since_epoch": 0},
+            "base_pricing": 35.0,
+            "currency_type": "usd"
+        },
+        {
+            "fp_id": "draft-d",
+            "vertiport_depart_id": "vertiport-1",
+            "vertiport_arrive_id": "vertiport-2",
+            "timestamp_depart": {"secs_since_epoch": 1640996100, "nanos_since_epoch": 0},
+            "timestamp_arrive": {"secs_since_epoch": 1640996400, "nanos_since_epoch": 0},
+            "base_pricing": 30.0,
+            "currency_type": "usd"
+        }
+    ]"#;
+
+    fn customer(behavior: BehaviorProfile) -> Customer {
+        let mut rng = StdRng::seed_from_u64(1);
+        Customer::generate("test", behavior, at(0), &mut rng)
+    }
+
+    #[tokio::test]
+    async fn rejected_stale_draft_queries_again() {
+        let dispatcher = stub_service(&[("/cargo/confirm", 400, "draft expired")]);
+        let mut c = customer(BehaviorProfile::default());
+        c.status = CustomerStatus::Confirm;
+        c.flights = serde_json::from_str(FLIGHTS).unwrap();
+
+        // Drafts are expected to last 30s
+        c.next(at(60), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Query);
+        assert_eq!(c.retries, 1);
+        assert_eq!(c.stats.retries, 0);
+    }
+
+    #[tokio::test]
+    async fn failures_count_as_retries_until_the_last() {
+        let dispatcher = stub_service(&[("/cargo/confirm", 500, "")]);
+        let behavior = BehaviorProfile {
+            retries: 2,
+            ..BehaviorProfile::default()
+        };
+        let mut c = customer(behavior);
+        c.status = CustomerStatus::Confirm;
+        c.flights = serde_json::from_str(FLIGHTS).unwrap();
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Confirm);
+        assert_eq!(c.stats.retries, 1);
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Done);
+        assert_eq!(c.stats.retries, 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_fresh_draft_is_a_failure() {
+        let dispatcher = stub_service(&[("/cargo/confirm", 400, "bad draft")]);
+        let mut c = customer(BehaviorProfile::default());
+        c.status = CustomerStatus::Confirm;
+        c.flights = serde_json::from_str(FLIGHTS).unwrap();
+
+        c.next(at(10), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Done);
+        assert_eq!(c.stats.retries, 0);
+    }
+
+    /// Queries and confirms a flight, returning when it departs
+    async fn confirmed_departure(behavior: BehaviorProfile) -> Option<chrono::NaiveDateTime> {
+        let dispatcher = stub_service(&[
+            ("/cargo/query", 200, OPTIONS),
+            ("/cargo/confirm", 200, "fp-1"),
+        ]);
+        let mut c = customer(behavior);
+        c.status = CustomerStatus::Query;
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Confirm);
+        c.next(at(1), &dispatcher).await;
+        assert!(c.stats.confirmed);
+        c.departure
+    }
+
+    #[tokio::test]
+    async fn each_selection_confirms_its_option() {
+        for (selection, max_price_usd, departs) in [
+            (Selection::First, None, 240),
+            (Selection::Cheapest, None, 900),
+            (Selection::EarliestArrival, None, 300),
+            (Selection::LatestDeparture, None, 400),
+            (Selection::PriceThreshold, Some(40.0), 500),
+        ] {
+            let behavior = BehaviorProfile {
+                selection,
+                max_price_usd,
+                ..BehaviorProfile::default()
+            };
+
+            let departure = confirmed_departure(behavior).await;
+            assert_eq!(departure, Some(at(departs)), "{:?}", selection);
+        }
+    }
+
+    #[tokio::test]
+    async fn unexpected_responses_are_failures() {
+        let dispatcher = stub_service(&[
+            ("/rideshare/vertiports", 200, "<html>maintenance</html>"),
+            ("/rideshare/query", 200, r#"{"rides": []}"#),
+        ]);
+        let behavior = BehaviorProfile {
+            service: Service::Rideshare,
+            retries: 3,
+            ..BehaviorProfile::default()
+        };
+        let mut c = customer(behavior);
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Vertiports);
+        assert_eq!(c.stats.retries, 1);
+
+        c.status = CustomerStatus::Query;
+        c.next(at(1), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Query);
+        assert_eq!(c.stats.retries, 2);
+    }
+
+    #[tokio::test]
+    async fn refused_modify_still_decides_whether_to_cancel() {
+        let dispatcher = stub_service(&[("/cargo/modify", 500, "")]);
+        let mut c = customer(BehaviorProfile::default());
+        c.status = CustomerStatus::Modify;
+        c.fp_id = "fp-1".to_string();
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Cancel);
+        assert!(c.stats.modify_failed);
+        assert!(!c.stats.modified);
+        assert_eq!(c.retries, 1);
+    }
+
+    /// A customer booked on a flight from vertiport-1 in a storm there
+    fn booked_in_a_storm(weather_cancel_chance: f32) -> (Customer, Dispatcher) {
+        let dispatcher = stub_service(&[("/cargo/cancel", 200, "")]);
+        let storm = r#"{"wind_speed_kts": 40.0, "vertiports": ["vertiport-1"]}"#;
+        dispatcher
+            .world
+            .add_weather(serde_json::from_str(storm).unwrap());
+
+        let behavior = BehaviorProfile {
+            weather_cancel_chance,
+            ..BehaviorProfile::builtin("greedy").unwrap()
+        };
+        let mut c = customer(behavior);
+        c.status = CustomerStatus::Booked;
+        c.fp_id = "fp-1".to_string();
+        c.departure = Some(at(600));
+        c.route = ["vertiport-1", "vertiport-2"]
+            .iter()
+            .map(|id| Vertiport {
+                id: id.to_string(),
+                label: id.to_string(),
+                latitude: 0.0,
+                longitude: 0.0,
+            })
+            .collect();
+
+        (c, dispatcher)
+    }
+
+    #[test]
+    fn builtin_behaviors_react_to_the_weather() {
+        for name in ["greedy", "mistake", "indecisive"] {
+            let behavior = BehaviorProfile::builtin(name).unwrap();
+            assert!(behavior.weather_cancel_chance > 0.0, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn booked_customer_cancels_in_bad_weather() {
+        let (mut c, dispatcher) = booked_in_a_storm(1.0);
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Cancel);
+
+        c.next(at(1), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Done);
+        assert!(c.stats.cancelled);
+        assert!(!c.stats.disrupted);
+    }
+
+    #[tokio::test]
+    async fn booked_customer_weighs_up_each_spell_of_bad_weather_once() {
+        let (mut c, dispatcher) = booked_in_a_storm(0.0);
+
+        c.next(at(0), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Booked);
+        assert!(c.weather_considered);
+
+        // Still the same storm, so no change of heart
+        c.behavior = Box::new(BehaviorProfile {
+            weather_cancel_chance: 1.0,
+            ..BehaviorProfile::default()
+        });
+        c.next(at(60), &dispatcher).await;
+        assert_eq!(c.status, CustomerStatus::Booked);
+    }
+
+    #[test]
+    fn profile_rejects_unknown_fields() {
+        assert!(serde_yaml::from_str::<BehaviorProfile>("selection: cheapest").is_ok());
+        assert!(serde_yaml::from_str::<BehaviorProfile>("selecton: cheapest").is_err());
+        assert!(serde_yaml::from_str::<BehaviorProfile>("cancel_chace: 0.5").is_err());
+
+        let typo = "think_time:\n  confrm:\n    type: fixed\n    duration_s: 1";
+        assert!(serde_yaml::from_str::<BehaviorProfile>(typo).is_err());
     }
 }
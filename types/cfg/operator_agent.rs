@@ -0,0 +1,186 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use hyper::{StatusCode, body};
+use serde::{Deserialize, Serialize};
+use uuid::{Builder, Uuid};
+
+use svc_cargo_client_rest::types::{VertiportsQuery, Vertiport};
+
+use crate::event_types::aircraft_events::AircraftRegistration;
+use crate::event_types::assets_events::AssetsEvent;
+use crate::event_types::Dispatcher;
+use crate::eel_types::EelEventType;
+use crate::cfg_types::Delay;
+
+/// What an operator does with svc-assets
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorRole {
+    /// Registers aircraft, like the PlayBoy locust user
+    PlayBoy,
+
+    /// Lists vertiports, like the Landlord locust user
+    Landlord,
+}
+
+/// A group of identical operators, declared in the configuration file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorConfig {
+    /// What the operators do
+    pub role: OperatorRole,
+
+    /// Number of operators in the group
+    #[serde(default = "default_operator_count")]
+    pub count: u32,
+
+    /// Time between requests (0.5 to 5 seconds if not provided)
+    #[serde(default = "default_operator_wait")]
+    pub wait: Delay,
+}
+
+fn default_operator_count() -> u32 {
+    1
+}
+
+fn default_operator_wait() -> Delay {
+    // Same as the locust users
+    Delay::Uniform {
+        min_s: 0.5,
+        max_s: 5.0,
+    }
+}
+
+impl OperatorConfig {
+    /// Checks that the group's values are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if self.count == 0 {
+            return Err("count must be at least 1".to_string());
+        }
+
+        self.wait.validate()
+    }
+}
+
+/// A fleet or vertiport operator that keeps sending requests to
+///  svc-assets for the whole simulation
+#[derive(Debug)]
+pub struct Operator {
+    id: Uuid,
+    role: OperatorRole,
+    wait: Delay,
+    next_action_time: chrono::NaiveDateTime,
+    rng: StdRng,
+}
+
+impl Operator {
+    /// Creates an operator from its group's configuration
+    /// # Arguments
+    /// * config - The operator's group
+    /// * current_time - The simulation time at which the operator is created
+    /// * rng - The simulation's seeded RNG, used to seed the operator's own RNG
+    pub fn generate(
+        config: &OperatorConfig,
+        current_time: chrono::NaiveDateTime,
+        rng: &mut StdRng
+    ) -> Self {
+        let mut rng = StdRng::seed_from_u64(rng.gen());
+        let uuid = Builder::from_random_bytes(rng.gen()).into_uuid();
+        println!("Creating {:?} operator {}", config.role, uuid);
+
+        Operator {
+            id: uuid,
+            role: config.role,
+            wait: config.wait,
+            next_action_time: current_time,
+            rng,
+        }
+    }
+
+    /// Prints an operator ID and log message to stdout
+    pub fn log(&self, s: &str) {
+        println!("{:?}: {}", self.id, s);
+    }
+
+    /// The simulation time at which the operator next wants to act
+    pub fn next_action_time(&self) -> chrono::NaiveDateTime {
+        self.next_action_time
+    }
+
+    /// Registers a new aircraft with a random owner
+    async fn register_aircraft(&mut self, dispatcher: &Dispatcher, now: chrono::NaiveDateTime) {
+        let owner = Builder::from_random_bytes(self.rng.gen()).into_uuid();
+        let registration = AircraftRegistration {
+            manufacturer: "Boeing".to_string(),
+            model: "747".to_string(),
+            registration_number: format!("N{:05}", self.rng.gen_range(0..100000)),
+            max_payload_kg: 100000.0,
+            max_range_km: 10000.0,
+            owner: owner.to_string(),
+            serial_number: "12345".to_string(),
+            status: "Available".to_string(),
+            whitelist: vec![],
+        };
+
+        self.log(&format!("Registering aircraft {}...", registration.registration_number));
+        let event = AssetsEvent::RegisterAircraft(registration);
+        match dispatcher.dispatch(&EelEventType::AssetsEvent(event), now).await {
+            Ok(resp) if resp.status() == StatusCode::OK => self.log("Registered."),
+            Ok(resp) => self.log(&format!("Bad Response: {}", resp.status())),
+            Err(e) => self.log(&format!("Failed to register: {:?}", e)),
+        }
+    }
+
+    /// Queries for vertiports
+    async fn query_vertiports(&mut self, dispatcher: &Dispatcher, now: chrono::NaiveDateTime) {
+        let event = AssetsEvent::Vertiports(VertiportsQuery {
+            latitude: 0.,
+            longitude: 0.,
+        });
+
+        self.log("Querying vertiports...");
+        let resp = match dispatcher.dispatch(&EelEventType::AssetsEvent(event), now).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                self.log(&format!("Failed to query: {:?}", e));
+                return;
+            }
+        };
+
+        if resp.status() != StatusCode::OK {
+            self.log(&format!("Bad Response: {}", resp.status()));
+            return;
+        }
+
+        let bytes = body::to_bytes(resp.into_body()).await.unwrap_or_default();
+        match serde_json::from_slice::<Vec<Vertiport>>(&bytes) {
+            Ok(vertiports) => self.log(&format!("Received {} vertiports.", vertiports.len())),
+            Err(e) => self.log(&format!("Could not parse vertiports: {}", e)),
+        }
+    }
+
+    /// Prompts the operator to send its next request
+    /// # Arguments
+    /// * now - The current simulation time
+    /// * dispatcher - Sends the request and records the outcome
+    pub async fn next(&mut self, now: chrono::NaiveDateTime, dispatcher: &Dispatcher) {
+        match self.role {
+            OperatorRole::PlayBoy => self.register_aircraft(dispatcher, now).await,
+            OperatorRole::Landlord => self.query_vertiports(dispatcher, now).await,
+        }
+
+        let wait = self.wait.sample(&mut self.rng);
+        self.next_action_time = now + wait.max(chrono::Duration::milliseconds(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_rejects_unknown_fields() {
+        assert!(serde_yaml::from_str::<OperatorConfig>("role: landlord\ncount: 2").is_ok());
+        assert!(serde_yaml::from_str::<OperatorConfig>("role: landlord\ncuont: 2").is_err());
+    }
+}
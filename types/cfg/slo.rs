@@ -0,0 +1,391 @@
+use super::customer_agent::Funnel;
+use crate::metrics_types::{percentile, RequestRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A step of the booking funnel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FunnelStage {
+    /// Received a list of vertiports
+    GotVertiports,
+
+    /// Received flight options
+    GotOptions,
+
+    /// Confirmed a flight plan
+    Confirmed,
+
+    /// Changed a confirmed flight plan
+    Modified,
+
+    /// Cancelled a confirmed flight plan
+    Cancelled,
+
+    /// Kept a confirmed flight plan
+    Kept,
+
+    /// Lost a confirmed flight plan to the authorities
+    Disrupted,
+}
+
+impl FunnelStage {
+    /// The name of the stage as written in the configuration
+    pub fn name(&self) -> &'static str {
+        match self {
+            FunnelStage::GotVertiports => "got_vertiports",
+            FunnelStage::GotOptions => "got_options",
+            FunnelStage::Confirmed => "confirmed",
+            FunnelStage::Modified => "modified",
+            FunnelStage::Cancelled => "cancelled",
+            FunnelStage::Kept => "kept",
+            FunnelStage::Disrupted => "disrupted",
+        }
+    }
+
+    /// Number of customers in a funnel that reached this stage
+    pub fn count(&self, funnel: &Funnel) -> usize {
+        match self {
+            FunnelStage::GotVertiports => funnel.got_vertiports,
+            FunnelStage::GotOptions => funnel.got_options,
+            FunnelStage::Confirmed => funnel.confirmed,
+            FunnelStage::Modified => funnel.modified,
+            FunnelStage::Cancelled => funnel.cancelled,
+            FunnelStage::Kept => funnel.kept,
+            FunnelStage::Disrupted => funnel.disrupted,
+        }
+    }
+}
+
+/// A threshold a run must meet to pass
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SloAssertion {
+    /// A latency percentile of an endpoint is below `max_ms`
+    Latency {
+        /// Path of the endpoint, e.g. `/cargo/query`
+        endpoint: String,
+
+        /// Percentile to check (0 to 100)
+        percentile: f64,
+
+        /// Latency the percentile must stay below (milliseconds)
+        max_ms: f64,
+    },
+
+    /// The fraction of requests that failed is below `max`
+    ErrorRate {
+        /// Path of the endpoint (all requests if not provided)
+        #[serde(default)]
+        endpoint: Option<String>,
+
+        /// Error rate the run must stay below (0.0 to 1.0)
+        max: f64,
+    },
+
+    /// The fraction of customers of a type that reached a funnel stage
+    ///  is within bounds
+    Funnel {
+        /// The name of the customer behavior
+        customer_type: String,
+
+        /// The funnel stage to check
+        stage: FunnelStage,
+
+        /// Lowest allowed fraction of customers (0.0 to 1.0)
+        #[serde(default)]
+        min_ratio: Option<f64>,
+
+        /// Highest allowed fraction of customers (0.0 to 1.0)
+        #[serde(default)]
+        max_ratio: Option<f64>,
+    },
+}
+
+/// The outcome of checking one assertion
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SloResult {
+    /// What was asserted
+    pub assertion: String,
+
+    /// The measured value (`None` if there was nothing to measure)
+    pub actual: Option<f64>,
+
+    /// Whether the run met the threshold
+    pub passed: bool,
+}
+
+impl fmt::Display for SloAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SloAssertion::Latency {
+                endpoint,
+                percentile,
+                max_ms,
+            } => write!(f, "{endpoint} p{percentile} < {max_ms} ms"),
+            SloAssertion::ErrorRate { endpoint, max } => write!(
+                f,
+                "error rate of {} < {:.2}%",
+                endpoint.as_deref().unwrap_or("all requests"),
+                100.0 * max
+            ),
+            SloAssertion::Funnel {
+                customer_type,
+                stage,
+                min_ratio,
+                max_ratio,
+            } => {
+                write!(f, "{customer_type} {}", stage.name())?;
+                if let Some(min) = min_ratio {
+                    write!(f, " >= {:.1}%", 100.0 * min)?;
+                }
+
+                if let Some(max) = max_ratio {
+                    write!(f, " <= {:.1}%", 100.0 * max)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl SloAssertion {
+    /// Checks that the thresholds make sense
+    pub fn validate(&self) -> Result<(), String> {
+        let check_ratio = |name: &str, r: f64| {
+            if !(0.0..=1.0).contains(&r) {
+                Err(format!("{name} must be between 0 and 1, got {r}"))
+            } else {
+                Ok(())
+            }
+        };
+
+        match self {
+            SloAssertion::Latency {
+                percentile, max_ms, ..
+            } => {
+                if !(0.0..=100.0).contains(percentile) {
+                    return Err(format!(
+                        "percentile must be between 0 and 100, got {percentile}"
+                    ));
+                }
+
+                if *max_ms <= 0.0 || !max_ms.is_finite() {
+                    return Err(format!("max_ms must be positive, got {max_ms}"));
+                }
+
+                Ok(())
+            }
+            SloAssertion::ErrorRate { max, .. } => {
+                if *max <= 0.0 || *max > 1.0 {
+                    return Err(format!("max must be above 0 and at most 1, got {max}"));
+                }
+
+                Ok(())
+            }
+            SloAssertion::Funnel {
+                min_ratio,
+                max_ratio,
+                ..
+            } => {
+                if min_ratio.is_none() && max_ratio.is_none() {
+                    return Err("funnel assertion needs min_ratio or max_ratio".to_string());
+                }
+
+                if let Some(min) = min_ratio {
+                    check_ratio("min_ratio", *min)?;
+                }
+
+                if let Some(max) = max_ratio {
+                    check_ratio("max_ratio", *max)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the assertion against the results of a run
+    /// # Arguments
+    /// * records - Every request sent during the run
+    /// * funnels - How far customers of each type got
+    pub fn evaluate(
+        &self,
+        records: &[RequestRecord],
+        funnels: &BTreeMap<String, Funnel>,
+    ) -> SloResult {
+        let actual = match self {
+            SloAssertion::Latency {
+                endpoint,
+                percentile: p,
+                ..
+            } => {
+                let mut latencies: Vec<f64> = records
+                    .iter()
+                    .filter(|r| &r.endpoint == endpoint)
+                    .map(|r| r.latency_ms)
+                    .collect();
+                latencies.sort_by(|a, b| a.total_cmp(b));
+
+                if latencies.is_empty() {
+                    None
+                } else {
+                    Some(percentile(&latencies, *p))
+                }
+            }
+            SloAssertion::ErrorRate { endpoint, .. } => {
+                let matching: Vec<&RequestRecord> = records
+                    .iter()
+                    .filter(|r| !matches!(endpoint, Some(e) if e != &r.endpoint))
+                    .collect();
+
+                if matching.is_empty() {
+                    None
+                } else {
+                    let errors = matching.iter().filter(|r| r.is_error()).count();
+                    Some(errors as f64 / matching.len() as f64)
+                }
+            }
+            SloAssertion::Funnel {
+                customer_type,
+                stage,
+                ..
+            } => match funnels.get(customer_type) {
+                Some(funnel) if funnel.spawned > 0 => {
+                    Some(stage.count(funnel) as f64 / funnel.spawned as f64)
+                }
+                _ => None,
+            },
+        };
+
+        let passed = match (self, actual) {
+            (_, None) => false,
+            (SloAssertion::Latency { max_ms, .. }, Some(a)) => a < *max_ms,
+            (SloAssertion::ErrorRate { max, .. }, Some(a)) => a < *max,
+            (
+                SloAssertion::Funnel {
+                    min_ratio,
+                    max_ratio,
+                    ..
+                },
+                Some(a),
+            ) => {
+                !matches!(min_ratio, Some(min) if a < *min)
+                    && !matches!(max_ratio, Some(max) if a > *max)
+            }
+        };
+
+        SloResult {
+            assertion: self.to_string(),
+            actual,
+            passed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::record;
+
+    fn latency(max_ms: f64) -> SloAssertion {
+        SloAssertion::Latency {
+            endpoint: "/cargo/query".to_string(),
+            percentile: 95.0,
+            max_ms,
+        }
+    }
+
+    fn funnel(min_ratio: Option<f64>, max_ratio: Option<f64>) -> SloAssertion {
+        SloAssertion::Funnel {
+            customer_type: "greedy".to_string(),
+            stage: FunnelStage::Confirmed,
+            min_ratio,
+            max_ratio,
+        }
+    }
+
+    #[test]
+    fn latency_must_stay_below_the_threshold() {
+        let records: Vec<RequestRecord> = (1..=20)
+            .map(|i| record("/cargo/query", Some(200), 10.0 * i as f64))
+            .chain([record("/cargo/confirm", Some(200), 1000.0)])
+            .collect();
+        let funnels = BTreeMap::new();
+
+        // p95 of 10, 20, ..., 200 is 190
+        let result = latency(200.0).evaluate(&records, &funnels);
+        assert_eq!(result.actual, Some(190.0));
+        assert!(result.passed);
+        assert!(!latency(190.0).evaluate(&records, &funnels).passed);
+    }
+
+    #[test]
+    fn error_rate_must_stay_below_the_threshold() {
+        let records: Vec<RequestRecord> = (0..100)
+            .map(|i| match i {
+                0 => record("/cargo/query", Some(500), 1.0),
+                1 => record("/cargo/query", None, 1.0),
+                _ => record("/cargo/query", Some(200), 1.0),
+            })
+            .chain([record("/cargo/cancel", Some(404), 1.0)])
+            .collect();
+        let funnels = BTreeMap::new();
+        let error_rate = |endpoint: Option<&str>, max| SloAssertion::ErrorRate {
+            endpoint: endpoint.map(str::to_string),
+            max,
+        };
+
+        let result = error_rate(Some("/cargo/query"), 0.03).evaluate(&records, &funnels);
+        assert_eq!(result.actual, Some(0.02));
+        assert!(result.passed);
+        assert!(
+            !error_rate(Some("/cargo/query"), 0.02)
+                .evaluate(&records, &funnels)
+                .passed
+        );
+
+        let all = error_rate(None, 0.05).evaluate(&records, &funnels);
+        assert_eq!(all.actual, Some(3.0 / 101.0));
+    }
+
+    #[test]
+    fn funnel_bounds_are_inclusive() {
+        let funnel_of = |spawned, confirmed| Funnel {
+            spawned,
+            confirmed,
+            ..Funnel::default()
+        };
+        let funnels = BTreeMap::from([("greedy".to_string(), funnel_of(20, 19))]);
+
+        assert!(funnel(Some(0.95), None).evaluate(&[], &funnels).passed);
+        assert!(!funnel(Some(0.96), None).evaluate(&[], &funnels).passed);
+        assert!(funnel(None, Some(0.95)).evaluate(&[], &funnels).passed);
+        assert!(!funnel(Some(0.5), Some(0.9)).evaluate(&[], &funnels).passed);
+    }
+
+    #[test]
+    fn nothing_to_measure_fails() {
+        let funnels = BTreeMap::from([("greedy".to_string(), Funnel::default())]);
+
+        let result = latency(300.0).evaluate(&[], &funnels);
+        assert_eq!(result.actual, None);
+        assert!(!result.passed);
+        assert!(!funnel(Some(0.0), None).evaluate(&[], &funnels).passed);
+    }
+
+    #[test]
+    fn rejects_thresholds_that_cannot_pass() {
+        assert!(latency(0.0).validate().is_err());
+        assert!(funnel(None, None).validate().is_err());
+        assert!(funnel(Some(1.5), None).validate().is_err());
+
+        let error_rate = SloAssertion::ErrorRate {
+            endpoint: None,
+            max: 0.0,
+        };
+        assert!(error_rate.validate().is_err());
+    }
+}
@@ -1,9 +1,177 @@
 
 /// Configuration-driven simulations use agents
 pub mod customer_agent;
+
+/// Fleet and vertiport operators that load svc-assets
+pub mod operator_agent;
+
+/// Customers enter the simulation according to arrival processes
+pub mod arrivals;
+
+/// Thresholds a run must meet to pass
+pub mod slo;
+use crate::event_types::Targets;
+use arrivals::ArrivalProcess;
+use customer_agent::BehaviorProfile;
+use operator_agent::OperatorConfig;
+use slo::SloAssertion;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::BTreeMap;
 use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// How simulation time advances relative to wall-clock time
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockMode {
+    /// One simulated second per real second
+    Realtime,
+
+    /// Simulated time runs `speed` times faster than real time
+    Scaled,
+
+    /// Simulated time jumps straight to the next scheduled event
+    Virtual,
+}
+
+impl FromStr for ClockMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "realtime" => Ok(ClockMode::Realtime),
+            "scaled" => Ok(ClockMode::Scaled),
+            "virtual" => Ok(ClockMode::Virtual),
+            _ => Err(format!(
+                "unknown clock mode '{s}' (expected realtime, scaled or virtual)"
+            )),
+        }
+    }
+}
+
+/// Customer types to generate and how often each appears
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum CustomerTypes {
+    /// Each listed type is equally likely
+    Uniform(Vec<String>),
+
+    /// Each type is chosen in proportion to its weight
+    Weighted(BTreeMap<String, f64>),
+}
+
+impl CustomerTypes {
+    /// Customer type names with their relative weights
+    pub fn weights(&self) -> Vec<(String, f64)> {
+        match self {
+            CustomerTypes::Uniform(types) => types.iter().map(|t| (t.clone(), 1.0)).collect(),
+            CustomerTypes::Weighted(types) => types.iter().map(|(t, w)| (t.clone(), *w)).collect(),
+        }
+    }
+
+    /// A distribution over the indices of [`CustomerTypes::weights`]
+    pub fn distribution(&self) -> Result<WeightedIndex<f64>, String> {
+        let weights = self.weights();
+        if let Some((name, w)) = weights.iter().find(|(_, w)| *w < 0.0 || !w.is_finite()) {
+            return Err(format!(
+                "customer type '{name}' has invalid weight {w} (must be zero or positive)"
+            ));
+        }
+
+        WeightedIndex::new(weights.iter().map(|(_, w)| *w))
+            .map_err(|e| format!("invalid customer_types: {e}"))
+    }
+}
+
+/// A random length of (simulated) time
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delay {
+    /// Always the same length
+    Fixed {
+        /// Length of the delay in seconds
+        duration_s: f64,
+    },
+
+    /// Any length between two bounds, equally likely
+    Uniform {
+        /// Shortest delay in seconds
+        min_s: f64,
+
+        /// Longest delay in seconds
+        max_s: f64,
+    },
+
+    /// Mostly short delays with occasional long ones
+    Exponential {
+        /// Average delay in seconds
+        mean_s: f64,
+    },
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        Delay::Fixed { duration_s: 0.0 }
+    }
+}
+
+impl Delay {
+    /// Checks that the delay can't be negative
+    pub fn validate(&self) -> Result<(), String> {
+        let (lower, upper) = match *self {
+            Delay::Fixed { duration_s } => (duration_s, duration_s),
+            Delay::Uniform { min_s, max_s } => (min_s, max_s),
+            Delay::Exponential { mean_s } => (mean_s, mean_s),
+        };
+
+        if lower < 0.0 || !upper.is_finite() || lower > upper {
+            return Err(format!("invalid delay {:?}", self));
+        }
+
+        Ok(())
+    }
+
+    /// Draws a delay
+    /// # Arguments
+    /// * rng - Random number generator to draw from
+    pub fn sample(&self, rng: &mut StdRng) -> chrono::Duration {
+        let seconds = match *self {
+            Delay::Fixed { duration_s } => duration_s,
+            Delay::Uniform { min_s, max_s } => min_s + rng.gen::<f64>() * (max_s - min_s),
+            Delay::Exponential { mean_s } => -(1.0 - rng.gen::<f64>()).ln() * mean_s,
+        };
+
+        chrono::Duration::milliseconds((seconds * 1000.0) as i64)
+    }
+}
+
+/// Simulation Clock Settings
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ClockConfig {
+    /// How simulation time advances
+    pub mode: ClockMode,
+
+    /// Simulated seconds per real second (scaled mode only)
+    #[serde(default = "default_clock_speed")]
+    pub speed: f64,
+}
+
+fn default_clock_speed() -> f64 {
+    1.0
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        ClockConfig {
+            mode: ClockMode::Realtime,
+            speed: default_clock_speed(),
+        }
+    }
+}
 
 /// Configuration File Fields
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,10 +183,50 @@ pub struct Config {
     pub duration_s: u32,
 
     /// Number of customers to generate
+    ///  (the most to generate if `arrivals` are provided)
     pub n_customers: u32,
 
-    /// Types of customers allowed (list)
-    pub customer_types: Vec<String>
+    /// When customers enter the simulation (all at the start if not provided)
+    #[serde(default)]
+    pub arrivals: Vec<ArrivalProcess>,
+
+    /// Types of customers allowed (list, or map of type to weight)
+    pub customer_types: CustomerTypes,
+
+    /// Customer behaviors, in addition to the built-in
+    ///  `greedy`, `mistake` and `indecisive` behaviors
+    #[serde(default)]
+    pub behaviors: BTreeMap<String, BehaviorProfile>,
+
+    /// Fleet and vertiport operators acting alongside the customers
+    #[serde(default)]
+    pub operators: Vec<OperatorConfig>,
+
+    /// Seed for the random number generator (random if not provided)
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// How simulation time advances (real time if not provided)
+    #[serde(default)]
+    pub clock: ClockConfig,
+
+    /// Maximum number of customers acting at once (unlimited if not provided)
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+
+    /// Services to send requests to (local defaults if not provided)
+    #[serde(default)]
+    pub targets: Targets,
+
+    /// EEL file of events (e.g. weather) to play out alongside the
+    ///  customers (captures and expectations are not used)
+    #[serde(default)]
+    pub scenario: Option<String>,
+
+    /// Assertions checked at the end of the run; any failure makes
+    ///  the simulation exit with an error
+    #[serde(default)]
+    pub slo: Vec<SloAssertion>,
 }
 
 // impl Default for Config {
@@ -32,12 +240,25 @@ pub struct Config {
 // }
 
 impl Config {
-    /// Validate an EEL file given a filename
+    /// Validate a configuration file given a filename
     /// # Arguments
     ///
     /// * `fname` - The name of a sim configuration YAML file
     #[allow(dead_code)]
     pub fn from_filename(fname: &str) -> Result<Self, Error> {
+        let config = Config::parse_filename(fname)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a configuration file given a filename, without validating it
+    ///
+    /// Lets values such as service URLs be overridden before the
+    ///  configuration is validated.
+    /// # Arguments
+    ///
+    /// * `fname` - The name of a sim configuration YAML file
+    pub fn parse_filename(fname: &str) -> Result<Self, Error> {
         // Read in File to be parsed
         let input_str = std::fs::read_to_string(fname)?;
 
@@ -58,9 +279,100 @@ impl Config {
         //     }
         // }
 
-        match serde_yaml::from_str::<Config>(&input_str) {
-            Ok(e) => Ok(e),
-            Err(e) => Err(Error::new(ErrorKind::InvalidInput, e)),
+        let config = match serde_yaml::from_str::<Config>(&input_str) {
+            Ok(e) => e,
+            Err(e) => return Err(Error::new(ErrorKind::InvalidInput, e)),
+        };
+
+        Ok(config)
+    }
+
+    /// Looks up a customer behavior by name
+    ///
+    /// Behaviors declared in the configuration take precedence over
+    ///  built-in behaviors of the same name.
+    pub fn behavior(&self, name: &str) -> Option<BehaviorProfile> {
+        self.behaviors
+            .get(name)
+            .copied()
+            .or_else(|| BehaviorProfile::builtin(name))
+    }
+
+    /// Check constraints between fields that can't be expressed in the types
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.clock.speed <= 0.0 || !self.clock.speed.is_finite() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("clock speed must be positive, got {}", self.clock.speed),
+            ));
+        }
+
+        if let Err(e) = self.customer_types.distribution() {
+            return Err(Error::new(ErrorKind::InvalidInput, e));
+        }
+
+        if let Err(e) = self.targets.validate() {
+            return Err(Error::new(ErrorKind::InvalidInput, e));
+        }
+
+        for (name, behavior) in &self.behaviors {
+            if let Err(e) = behavior.validate() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("behavior '{name}': {e}"),
+                ));
+            }
+        }
+
+        for (name, _) in self.customer_types.weights() {
+            if self.behavior(&name).is_none() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("customer type '{name}' has no matching behavior"),
+                ));
+            }
+        }
+
+        for operators in &self.operators {
+            if let Err(e) = operators.validate() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("operators '{:?}': {e}", operators.role),
+                ));
+            }
         }
+
+        for process in &self.arrivals {
+            if let Err(e) = process.validate(self.duration_s) {
+                return Err(Error::new(ErrorKind::InvalidInput, e));
+            }
+        }
+
+        for assertion in &self.slo {
+            if let Err(e) = assertion.validate() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("slo '{assertion}': {e}"),
+                ));
+            }
+
+            if let SloAssertion::Funnel { customer_type, .. } = assertion {
+                if !self.customer_types.weights().iter().any(|(t, _)| t == customer_type) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("slo '{assertion}': unknown customer type '{customer_type}'"),
+                    ));
+                }
+            }
+        }
+
+        if self.max_concurrency == Some(0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "max_concurrency must be at least 1",
+            ));
+        }
+
+        Ok(())
     }
 }
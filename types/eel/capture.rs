@@ -0,0 +1,238 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One step of a path into a JSON document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A field of an object, e.g. `.fp_id`
+    Field(String),
+
+    /// An element of an array, e.g. `[0]`
+    Index(usize),
+}
+
+/// Parses a path such as `$[0].fp_id`
+///
+/// Paths start at the document root `$` and are followed by any number
+///  of `.field` and `[index]` steps.
+pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let rest = match path.strip_prefix('$') {
+        Some(rest) => rest,
+        None => return Err(format!("path '{path}' must start with '$'")),
+    };
+
+    let mut segments = vec![];
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut field = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+
+                    field.push(c);
+                    chars.next();
+                }
+
+                if field.is_empty() {
+                    return Err(format!("path '{path}' has an empty field name"));
+                }
+
+                segments.push(PathSegment::Field(field));
+            }
+            '[' => {
+                let index: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                match index.parse::<usize>() {
+                    Ok(i) => segments.push(PathSegment::Index(i)),
+                    Err(_) => return Err(format!("path '{path}' has an invalid index '{index}'")),
+                }
+            }
+            _ => return Err(format!("path '{path}' has an unexpected '{c}'")),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Looks up the value at `path` in a JSON document
+///
+/// # Returns
+/// The value, or `None` if the document has nothing at that path
+pub fn json_path<'a>(document: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    let mut value = document;
+    for segment in parse_path(path)? {
+        let next = match segment {
+            PathSegment::Field(f) => value.get(f.as_str()),
+            PathSegment::Index(i) => value.get(i),
+        };
+
+        match next {
+            Some(v) => value = v,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(value))
+}
+
+/// Names of every `${name}` placeholder in a string
+pub fn placeholders(s: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        rest = &rest[start + 2..];
+        match rest.find('}') {
+            Some(end) => {
+                names.push(rest[..end].to_string());
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    names
+}
+
+/// Replaces every `${name}` placeholder in a string with its bound value
+pub fn substitute(s: &str, bindings: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+
+        let name = &after[..end];
+        match bindings.get(name) {
+            Some(value) => out.push_str(value),
+            None => return Err(format!("'{name}' has not been captured")),
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Applies `f` to every string value in a JSON document
+pub fn map_strings<F>(value: &Value, f: &mut F) -> Result<Value, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    Ok(match value {
+        Value::String(s) => Value::String(f(s)?),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|v| map_strings(v, f))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => {
+            let mut out = serde_json::Map::new();
+            for (k, v) in map {
+                out.insert(k.clone(), map_strings(v, f)?);
+            }
+
+            Value::Object(out)
+        }
+        other => other.clone(),
+    })
+}
+
+/// Parses a response body as JSON
+///
+/// A body that isn't JSON, such as a plain ID, becomes a JSON string.
+pub fn parse_body(body: &[u8]) -> Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into()))
+}
+
+/// The text to bind for a captured value
+///
+/// Strings are bound without their quotes; anything else as JSON.
+pub fn capture_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_fields_and_indices() {
+        assert_eq!(parse_path("$"), Ok(vec![]));
+        assert_eq!(
+            parse_path("$[0].fp_id"),
+            Ok(vec![
+                PathSegment::Index(0),
+                PathSegment::Field("fp_id".to_string())
+            ])
+        );
+        assert_eq!(
+            parse_path("$.options[12].id"),
+            Ok(vec![
+                PathSegment::Field("options".to_string()),
+                PathSegment::Index(12),
+                PathSegment::Field("id".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        for path in ["", "[0]", "$.", "$..id", "$[a]", "$[-1]", "$id"] {
+            assert!(parse_path(path).is_err(), "accepted '{path}'");
+        }
+    }
+
+    #[test]
+    fn looks_up_values() {
+        let document = json!([{"fp_id": "draft-1"}, {"fp_id": "draft-2"}]);
+        assert_eq!(
+            json_path(&document, "$[1].fp_id"),
+            Ok(Some(&json!("draft-2")))
+        );
+        assert_eq!(json_path(&document, "$[2].fp_id"), Ok(None));
+        assert_eq!(json_path(&document, "$.fp_id"), Ok(None));
+        assert_eq!(capture_text(&json!("draft-1")), "draft-1");
+        assert_eq!(capture_text(&json!(12)), "12");
+        assert_eq!(parse_body(b"fp-1"), json!("fp-1"));
+    }
+
+    #[test]
+    fn substitutes_bound_placeholders() {
+        let bindings = BTreeMap::from([
+            ("draft".to_string(), "draft-1".to_string()),
+            ("plan".to_string(), "fp-1".to_string()),
+        ]);
+
+        assert_eq!(substitute("${draft}", &bindings), Ok("draft-1".to_string()));
+        assert_eq!(
+            substitute("/plans/${plan}?draft=${draft}", &bindings),
+            Ok("/plans/fp-1?draft=draft-1".to_string())
+        );
+        assert_eq!(
+            substitute("no placeholders", &bindings),
+            Ok("no placeholders".to_string())
+        );
+        assert_eq!(
+            substitute("unclosed ${draft", &bindings),
+            Ok("unclosed ${draft".to_string())
+        );
+        assert!(substitute("${missing}", &bindings).is_err());
+        assert_eq!(placeholders("${plan}-${draft}"), vec!["plan", "draft"]);
+    }
+}
@@ -0,0 +1,215 @@
+use super::capture::{json_path, parse_body, parse_path};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What an event's response should look like
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Expectation {
+    /// HTTP status code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Values in the response body, as a map of path to value
+    ///  (e.g. `"$[0].currency_type": "usd"`)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub body: BTreeMap<String, Value>,
+
+    /// Number of elements in the response body, such as flight options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+
+    /// Longest the response may take (milliseconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<f64>,
+}
+
+/// Whether an event's response met its expectation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExpectationResult {
+    /// Position of the event in the EEL file
+    pub index: usize,
+
+    /// The timestamp of the event
+    pub timestamp: NaiveDateTime,
+
+    /// Whether every expectation was met
+    pub passed: bool,
+
+    /// What didn't match
+    pub failures: Vec<String>,
+}
+
+impl Expectation {
+    /// Checks that every body path is valid
+    pub fn validate(&self) -> Result<(), String> {
+        for path in self.body.keys() {
+            parse_path(path)?;
+        }
+
+        if matches!(self.max_latency_ms, Some(l) if l < 0.0 || !l.is_finite()) {
+            return Err("max_latency_ms must be zero or positive".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Compares a response against the expectation
+    /// # Arguments
+    /// * status - The HTTP status code (`None` if no response was received)
+    /// * body - The response body (`None` if no response was received)
+    /// * latency_ms - How long the response took (milliseconds)
+    ///
+    /// # Returns
+    /// A description of each expectation that wasn't met
+    pub fn check(&self, status: Option<u16>, body: Option<&[u8]>, latency_ms: f64) -> Vec<String> {
+        let mut failures = vec![];
+
+        if let Some(expected) = self.status {
+            match status {
+                Some(s) if s == expected => {}
+                Some(s) => failures.push(format!("status {s} (expected {expected})")),
+                None => failures.push(format!("no response (expected status {expected})")),
+            }
+        }
+
+        if let Some(max) = self.max_latency_ms {
+            if latency_ms > max {
+                failures.push(format!(
+                    "took {latency_ms:.1} ms (expected at most {max} ms)"
+                ));
+            }
+        }
+
+        if self.body.is_empty() && self.count.is_none() {
+            return failures;
+        }
+
+        let document = match body {
+            Some(b) => parse_body(b),
+            None => {
+                failures.push("no response body to check".to_string());
+                return failures;
+            }
+        };
+
+        if let Some(expected) = self.count {
+            match document.as_array() {
+                Some(a) if a.len() == expected => {}
+                Some(a) => failures.push(format!("{} elements (expected {expected})", a.len())),
+                None => failures.push(format!("body is not a list (expected {expected} elements)")),
+            }
+        }
+
+        for (path, expected) in &self.body {
+            match json_path(&document, path) {
+                Ok(Some(v)) if v == expected => {}
+                Ok(Some(v)) => failures.push(format!("{path} is {v} (expected {expected})")),
+                Ok(None) => failures.push(format!("{path} is missing (expected {expected})")),
+                Err(e) => failures.push(e),
+            }
+        }
+
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const OPTIONS: &[u8] =
+        br#"[{"fp_id": "draft-1", "currency_type": "usd"}, {"fp_id": "draft-2"}]"#;
+
+    #[test]
+    fn empty_expectation_always_passes() {
+        let expect = Expectation::default();
+        assert!(expect.check(None, None, 1e6).is_empty());
+    }
+
+    #[test]
+    fn matching_response_passes() {
+        let expect = Expectation {
+            status: Some(200),
+            body: BTreeMap::from([("$[0].currency_type".to_string(), json!("usd"))]),
+            count: Some(2),
+            max_latency_ms: Some(100.0),
+        };
+
+        assert!(expect.check(Some(200), Some(OPTIONS), 100.0).is_empty());
+    }
+
+    #[test]
+    fn reports_each_mismatch() {
+        let expect = Expectation {
+            status: Some(200),
+            body: BTreeMap::from([
+                ("$[0].fp_id".to_string(), json!("draft-2")),
+                ("$[1].currency_type".to_string(), json!("usd")),
+            ]),
+            count: Some(3),
+            max_latency_ms: Some(50.0),
+        };
+
+        let failures = expect.check(Some(500), Some(OPTIONS), 75.0);
+        assert_eq!(
+            failures,
+            vec![
+                "status 500 (expected 200)",
+                "took 75.0 ms (expected at most 50 ms)",
+                "2 elements (expected 3)",
+                "$[0].fp_id is \"draft-1\" (expected \"draft-2\")",
+                "$[1].currency_type is missing (expected \"usd\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_response_fails() {
+        let expect = Expectation {
+            status: Some(200),
+            count: Some(2),
+            ..Expectation::default()
+        };
+
+        assert_eq!(
+            expect.check(None, None, 0.0),
+            vec![
+                "no response (expected status 200)",
+                "no response body to check"
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_body_is_a_string() {
+        let expect = Expectation {
+            body: BTreeMap::from([("$".to_string(), json!("fp-1"))]),
+            count: Some(1),
+            ..Expectation::default()
+        };
+
+        assert_eq!(
+            expect.check(Some(200), Some(b"fp-1"), 0.0),
+            vec!["body is not a list (expected 1 elements)"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expectations() {
+        let bad_path = Expectation {
+            body: BTreeMap::from([("fp_id".to_string(), json!("draft-1"))]),
+            ..Expectation::default()
+        };
+        assert!(bad_path.validate().is_err());
+
+        let bad_latency = Expectation {
+            max_latency_ms: Some(-1.0),
+            ..Expectation::default()
+        };
+        assert!(bad_latency.validate().is_err());
+    }
+}
@@ -1,32 +1,96 @@
 /// Eel files include customer events
-pub mod customer_events {
-    include!("../events/customer_events.rs");
-}
+pub use crate::event_types::customer_events;
+
+/// Values captured from responses and substituted into later events
+pub mod capture;
+
+/// Checks on the responses to events
+pub mod expect;
 
 pub use customer_events::*;
+pub use crate::event_types::weather_events::{self, WeatherEvent};
+pub use crate::event_types::authority_events::{self, AuthorityEvent};
+pub use crate::event_types::aircraft_events::{self, AircraftEvent};
+pub use crate::event_types::assets_events::{self, AssetsEvent};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use expect::Expectation;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
 
 ///////////////////////////////////////////////////////////////////////
 /// EEL File
 ///////////////////////////////////////////////////////////////////////
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum EelEventType {
     /// Events triggered by a customer
     CustomerEvent(CustomerEvent),
-    // WeatherEvent
-    // AuthorityEvent
+
+    /// Changes in the weather
+    WeatherEvent(WeatherEvent),
+
+    /// Restrictions and decisions from civil aviation authorities
+    AuthorityEvent(AuthorityEvent),
+
+    /// Changes to the fleet of aircraft
+    AircraftEvent(AircraftEvent),
+
+    /// Requests from fleet and vertiport operators
+    AssetsEvent(AssetsEvent),
 }
 
 /// External Event
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EelEvent {
     /// The type of event
     pub event: EelEventType,
 
     /// The timestamp of the event
     pub timestamp: NaiveDateTime,
+
+    /// Values to capture from the response, as a map of name to path
+    ///  (e.g. `"draft": "$[0].fp_id"`), for later events to refer to
+    ///  as `${draft}`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub capture: BTreeMap<String, String>,
+
+    /// What the response should look like (not checked if not provided)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<Expectation>,
+}
+
+impl EelEvent {
+    /// Names of the captured values this event and its expectation refer to
+    pub fn references(&self) -> Vec<String> {
+        let mut names = vec![];
+        if let Ok(value) = serde_json::to_value((&self.event, &self.expect)) {
+            let _ = capture::map_strings(&value, &mut |s| {
+                names.extend(capture::placeholders(s));
+                Ok(s.to_string())
+            });
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The event and its expectation with every `${name}` replaced by
+    ///  its captured value
+    /// # Arguments
+    /// * bindings - Captured values by name
+    pub fn resolve(
+        &self,
+        bindings: &BTreeMap<String, String>,
+    ) -> Result<(EelEventType, Option<Expectation>), String> {
+        let value =
+            serde_json::to_value((&self.event, &self.expect)).map_err(|e| e.to_string())?;
+        let value = capture::map_strings(&value, &mut |s| capture::substitute(s, bindings))?;
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
 }
 
 /// External Event Log
@@ -43,6 +107,19 @@ impl Eel {
     ///
     /// * `fname` - The name of a sim configuration YAML file
     pub fn from_filename(fname: &str) -> Result<Self, serde_json::Error> {
+        let eel = Eel::parse_filename(fname)?;
+        if let Err(e) = eel.validate() {
+            return Err(<serde_json::Error as serde::de::Error>::custom(e));
+        }
+
+        Ok(eel)
+    }
+
+    /// Parses an EEL file given a filename, without validating it
+    /// # Arguments
+    ///
+    /// * `fname` - The name of an EEL JSON file
+    pub fn parse_filename(fname: &str) -> Result<Self, serde_json::Error> {
         // Read in File to be parsed
         let input_str = match std::fs::read_to_string(fname) {
             Err(_) => "".to_string(),
@@ -51,4 +128,302 @@ impl Eel {
 
         serde_json::from_str::<Eel>(&input_str)
     }
+
+    /// Checks that every captured value is captured once, with a valid
+    ///  path, no later than the events that refer to it, and that
+    ///  expectations are well formed
+    pub fn validate(&self) -> Result<(), String> {
+        let mut captured_at: BTreeMap<&str, NaiveDateTime> = BTreeMap::new();
+        for e in &self.events {
+            for (name, path) in &e.capture {
+                capture::parse_path(path).map_err(|err| format!("capture '{name}': {err}"))?;
+                if captured_at.insert(name, e.timestamp).is_some() {
+                    return Err(format!("'{name}' is captured by more than one event"));
+                }
+            }
+
+            if let Some(expect) = &e.expect {
+                expect
+                    .validate()
+                    .map_err(|err| format!("event at {} expects: {err}", e.timestamp))?;
+            }
+        }
+
+        for e in &self.events {
+            for name in e.references() {
+                if e.capture.contains_key(&name) {
+                    return Err(format!(
+                        "event at {} refers to '{name}', which it captures itself",
+                        e.timestamp
+                    ));
+                }
+
+                match captured_at.get(name.as_str()) {
+                    None => {
+                        return Err(format!(
+                            "event at {} refers to '{name}', which is never captured",
+                            e.timestamp
+                        ))
+                    }
+                    Some(t) if *t > e.timestamp => {
+                        return Err(format!(
+                            "event at {} refers to '{name}', which is captured later at {t}",
+                            e.timestamp
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the EEL to a file
+    /// # Arguments
+    ///
+    /// * `fname` - The name of the EEL JSON file to write
+    pub fn to_filename(&self, fname: &str) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(fname)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// Captures events as they are dispatched so a run can be replayed
+///
+/// Identifiers the services return are captured from the responses, and
+///  later events that send them back refer to the captured value, so a
+///  recording can be replayed against a fresh service.
+///
+/// Clones share the same underlying events.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    events: Arc<Mutex<Vec<EelEvent>>>,
+    responses: Arc<Mutex<BTreeMap<usize, serde_json::Value>>>,
+}
+
+impl Recorder {
+    /// Creates an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event at the simulation time it was dispatched
+    /// # Returns
+    /// The index of the event, to pass its response to [`Recorder::respond`]
+    pub fn record(&self, event: EelEventType, timestamp: NaiveDateTime) -> usize {
+        let mut events = self.events.lock().unwrap();
+        events.push(EelEvent {
+            event,
+            timestamp,
+            capture: BTreeMap::new(),
+            expect: None,
+        });
+
+        events.len() - 1
+    }
+
+    /// Keeps the successful response to a recorded event, to capture
+    ///  identifiers from
+    /// # Arguments
+    /// * index - The index returned by [`Recorder::record`]
+    /// * body - The response body
+    pub fn respond(&self, index: usize, body: &[u8]) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(index, capture::parse_body(body));
+    }
+
+    /// An EEL of every event recorded so far, earliest first
+    ///
+    /// Events at the same time keep the order they were dispatched in.
+    ///  A value sent in an event that an earlier response returned as an
+    ///  identifier is replaced by a `${name}` placeholder, captured from
+    ///  the latest such response. Identifiers a response merely echoes
+    ///  from its request aren't captured.
+    pub fn eel(&self) -> Eel {
+        let mut events: Vec<(usize, EelEvent)> = self
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .enumerate()
+            .collect();
+        events.sort_by_key(|(_, e)| e.timestamp);
+
+        let responses = self.responses.lock().unwrap();
+
+        // The latest event and path that returned each identifier
+        let mut sources: BTreeMap<String, (usize, String)> = BTreeMap::new();
+
+        // Names of the values to capture, by event and path
+        let mut names: BTreeMap<(usize, String), String> = BTreeMap::new();
+        for (position, (index, e)) in events.iter_mut().enumerate() {
+            // Values the event sent, which its response may echo back
+            let mut sent: BTreeSet<String> = BTreeSet::new();
+            let replaced = serde_json::to_value(&e.event)
+                .map_err(|err| err.to_string())
+                .and_then(|value| {
+                    capture::map_strings(&value, &mut |s| {
+                        sent.insert(s.to_string());
+                        let source = match sources.get(s) {
+                            Some(source) => source,
+                            None => return Ok(s.to_string()),
+                        };
+
+                        let count = names.len();
+                        let name = names.entry(source.clone()).or_insert_with(|| {
+                            let field = match source.1.rsplit_once('.') {
+                                Some((_, field)) => field,
+                                None => "response",
+                            };
+
+                            format!("{}_{}", field, count + 1)
+                        });
+
+                        Ok(format!("${{{name}}}"))
+                    })
+                })
+                .and_then(|value| serde_json::from_value(value).map_err(|err| err.to_string()));
+
+            match replaced {
+                Ok(event) => e.event = event,
+                Err(err) => println!("Could not refer to captured values: {}", err),
+            }
+
+            if let Some(response) = responses.get(index) {
+                for (path, id) in identifiers(response) {
+                    if !sent.contains(&id) {
+                        sources.insert(id, (position, path));
+                    }
+                }
+            }
+        }
+
+        for ((position, path), name) in names {
+            events[position].1.capture.insert(name, path);
+        }
+
+        Eel {
+            events: events.into_iter().map(|(_, e)| e).collect(),
+        }
+    }
+}
+
+/// Identifiers in a response and their paths: fields named `id` or
+///  ending in `_id`, or the whole response if it is plain text
+fn identifiers(document: &serde_json::Value) -> Vec<(String, String)> {
+    fn walk(value: &serde_json::Value, path: &str, out: &mut Vec<(String, String)>) {
+        match value {
+            serde_json::Value::Array(values) => {
+                for (i, v) in values.iter().enumerate() {
+                    walk(v, &format!("{path}[{i}]"), out);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    // Keys the capture path syntax can't express
+                    if k.is_empty() || k.contains(['.', '[']) {
+                        continue;
+                    }
+
+                    let path = format!("{path}.{k}");
+                    match v {
+                        serde_json::Value::String(s)
+                            if (k == "id" || k.ends_with("_id")) && !s.is_empty() =>
+                        {
+                            out.push((path, s.clone()))
+                        }
+                        _ => walk(v, &path, out),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = vec![];
+    match document {
+        serde_json::Value::String(s) if !s.is_empty() => out.push(("$".to_string(), s.clone())),
+        _ => walk(document, "$", &mut out),
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+    use svc_cargo_client_rest::types::{FlightCancel, FlightConfirm, VertiportsQuery};
+
+    fn cargo(request: CargoRequest) -> EelEventType {
+        EelEventType::CustomerEvent(CustomerEvent::CargoRequest(request))
+    }
+
+    #[test]
+    fn recordings_capture_identifiers_for_later_events() {
+        let recorder = Recorder::new();
+        let query = recorder.record(
+            cargo(CargoRequest::Vertiports(VertiportsQuery {
+                latitude: 0.,
+                longitude: 0.,
+            })),
+            at(0),
+        );
+        recorder.respond(query, br#"[{"fp_id": "draft-1"}, {"fp_id": "draft-2"}]"#);
+
+        let confirm = recorder.record(
+            cargo(CargoRequest::Confirm(FlightConfirm {
+                fp_id: "draft-2".to_string(),
+            })),
+            at(10),
+        );
+        recorder.respond(confirm, b"plan-1");
+
+        // Recorded out of order, but sent after the confirmation
+        let cancel = recorder.record(
+            cargo(CargoRequest::Cancel(FlightCancel {
+                fp_id: "plan-1".to_string(),
+            })),
+            at(20),
+        );
+        recorder.respond(cancel, br#"{"fp_id": "plan-1"}"#);
+        recorder.record(
+            cargo(CargoRequest::Cancel(FlightCancel {
+                fp_id: "unknown".to_string(),
+            })),
+            at(5),
+        );
+
+        let eel = recorder.eel();
+        assert!(eel.validate().is_ok());
+
+        let events: Vec<serde_json::Value> = eel
+            .events
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap())
+            .collect();
+        let request = |e: &serde_json::Value| e["event"]["CustomerEvent"]["CargoRequest"].clone();
+
+        assert_eq!(events[0]["capture"]["fp_id_1"], "$[1].fp_id");
+        assert_eq!(request(&events[1])["Cancel"]["fp_id"], "unknown");
+        assert_eq!(request(&events[2])["Confirm"]["fp_id"], "${fp_id_1}");
+        assert_eq!(events[2]["capture"]["response_2"], "$");
+        assert_eq!(request(&events[3])["Cancel"]["fp_id"], "${response_2}");
+
+        // Not captured from the echo in the last response
+        recorder.record(
+            cargo(CargoRequest::Cancel(FlightCancel {
+                fp_id: "plan-1".to_string(),
+            })),
+            at(30),
+        );
+        let eel = recorder.eel();
+        let last = serde_json::to_value(&eel.events[4]).unwrap();
+        assert_eq!(request(&last)["Cancel"]["fp_id"], "${response_2}");
+    }
 }
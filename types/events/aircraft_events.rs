@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use hyper::{Body, Response};
+use crate::event_types::{assets_events, Dispatcher};
+use crate::event_types::authority_events::in_window;
+
+///////////////////////////////////////////////////////////////////////
+/// Aircraft Events
+///////////////////////////////////////////////////////////////////////
+
+/// Changes to the fleet of aircraft
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum AircraftEvent {
+    /// Add a new aircraft to svc-assets
+    Register(AircraftRegistration),
+
+    /// Take an aircraft out of service
+    OutOfService(OutOfService),
+
+    /// Put an aircraft back into service
+    ReturnToService(ReturnToService),
+
+    /// Reduce how much charge an aircraft's battery can hold
+    BatteryDegradation(BatteryDegradation),
+
+    /// Hold up an aircraft on its way to a vertiport
+    DelayedArrival(DelayedArrival),
+}
+
+/// A new aircraft, as registered with svc-assets
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AircraftRegistration {
+    /// Manufacturer of the aircraft
+    pub manufacturer: String,
+
+    /// Model of the aircraft
+    pub model: String,
+
+    /// Registration number, used as the aircraft's ID by other events
+    pub registration_number: String,
+
+    /// Heaviest load the aircraft can carry (kilograms)
+    pub max_payload_kg: f32,
+
+    /// Furthest the aircraft can fly on a full charge (kilometers)
+    pub max_range_km: f32,
+
+    /// ID of the aircraft's owner
+    pub owner: String,
+
+    /// Manufacturer's serial number
+    pub serial_number: String,
+
+    /// Availability reported to svc-assets (e.g. `Available`)
+    pub status: String,
+
+    /// IDs of the operators allowed to use the aircraft
+    #[serde(default)]
+    pub whitelist: Vec<String>,
+}
+
+/// An aircraft going out of service
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutOfService {
+    /// The aircraft's ID
+    pub aircraft_id: String,
+
+    /// When the aircraft goes out of service (the event's timestamp if not provided)
+    #[serde(default)]
+    pub start: Option<NaiveDateTime>,
+
+    /// When the aircraft returns (on a return event if not provided)
+    #[serde(default)]
+    pub end: Option<NaiveDateTime>,
+
+    /// Why the aircraft is out of service
+    #[serde(default)]
+    pub reason: Option<String>,
+
+    /// The vertiport the aircraft is grounded at, if known
+    #[serde(default)]
+    pub vertiport_id: Option<String>,
+}
+
+/// An aircraft returning to service
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReturnToService {
+    /// The aircraft's ID
+    pub aircraft_id: String,
+}
+
+/// An aircraft's battery losing capacity
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatteryDegradation {
+    /// The aircraft's ID
+    pub aircraft_id: String,
+
+    /// Charge the battery can still hold, as a percentage of when new
+    pub capacity_percent: f32,
+}
+
+/// An aircraft arriving later than planned
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DelayedArrival {
+    /// The aircraft's ID
+    pub aircraft_id: String,
+
+    /// The vertiport the aircraft is headed to, which it can only fly
+    ///  from once it arrives
+    #[serde(default)]
+    pub vertiport_id: Option<String>,
+
+    /// How late the aircraft will be (seconds)
+    pub delay_s: u32,
+}
+
+impl OutOfService {
+    /// Whether the aircraft is out of service at time `t`
+    pub fn is_active(&self, t: NaiveDateTime) -> bool {
+        in_window(self.start, self.end, t)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////
+/// Actions on Aircraft Events
+///////////////////////////////////////////////////////////////////////
+
+/// Applies a change to the fleet in the simulated world
+///
+/// Registrations are sent to svc-assets, everything else is passed on
+///  to the environment stand-in, if one is targeted.
+/// # Arguments
+/// * event - The change to the fleet
+/// * dispatcher - Holds the simulated world and sends the request
+/// * sim_time - The simulation time at which the event occurs
+/// # Returns
+/// Result of the REST request
+pub async fn action(
+    event: &AircraftEvent,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let world = &dispatcher.world;
+    let (path, body) = match event {
+        AircraftEvent::Register(s) => {
+            return assets_events::register_aircraft(s, dispatcher, sim_time).await
+        }
+        AircraftEvent::OutOfService(s) => {
+            let mut outage = s.clone();
+            outage.start = outage.start.or(Some(sim_time));
+            world.take_out_of_service(outage.clone());
+            ("/aircraft/out_of_service", serde_json::to_string(&outage))
+        }
+        AircraftEvent::ReturnToService(s) => {
+            world.return_to_service(&s.aircraft_id, sim_time);
+            ("/aircraft/return_to_service", serde_json::to_string(&s))
+        }
+        AircraftEvent::BatteryDegradation(s) => {
+            world.degrade_battery(&s.aircraft_id, s.capacity_percent);
+            ("/aircraft/battery_degradation", serde_json::to_string(&s))
+        }
+        AircraftEvent::DelayedArrival(s) => {
+            let until = sim_time + chrono::Duration::seconds(i64::from(s.delay_s));
+            world.delay_aircraft(&s.aircraft_id, s.vertiport_id.clone(), until);
+            ("/aircraft/delayed_arrival", serde_json::to_string(&s))
+        }
+    };
+
+    dispatcher.publish(path, body.unwrap(), sim_time).await
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use hyper::{Body, Method, Response};
+use crate::event_types::Dispatcher;
+use crate::event_types::aircraft_events::AircraftRegistration;
+use crate::event_types::customer_events::cargo_client_types::VertiportsQuery;
+
+///////////////////////////////////////////////////////////////////////
+/// Assets Events
+///////////////////////////////////////////////////////////////////////
+
+/// Requests from fleet and vertiport operators, as the locust users in
+///  `load-balancing/assets.py` send them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum AssetsEvent {
+    /// Add a new aircraft
+    RegisterAircraft(AircraftRegistration),
+
+    /// Request list of vertiports from svc-cargo
+    Vertiports(VertiportsQuery),
+}
+
+///////////////////////////////////////////////////////////////////////
+/// Actions on Assets Events
+///////////////////////////////////////////////////////////////////////
+
+/// Registers an aircraft with svc-assets and, once it is accepted, adds
+///  it to the fleet in the simulated world
+/// # Arguments
+/// * registration - The new aircraft
+/// * dispatcher - Holds the simulated world and sends the request
+/// * sim_time - The simulation time at which the aircraft is registered
+/// # Returns
+/// Result of the REST request
+pub async fn register_aircraft(
+    registration: &AircraftRegistration,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let body = serde_json::to_string(registration).unwrap();
+    let resp = dispatcher
+        .send(&dispatcher.targets.assets, "/assets/aircraft", Method::POST, body, sim_time)
+        .await?;
+
+    if resp.status().is_success() {
+        dispatcher.world.register_aircraft(registration.clone());
+    }
+
+    Ok(resp)
+}
+
+/// Mimics a REST message from an operator
+/// # Arguments
+/// * event - The type of event to mimic
+/// * dispatcher - Sends the request and records the outcome
+/// * sim_time - The simulation time at which the event occurs
+/// # Returns
+/// Result of the REST request
+pub async fn action(
+    event: &AssetsEvent,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    match event {
+        AssetsEvent::RegisterAircraft(s) => register_aircraft(s, dispatcher, sim_time).await,
+        // Same request as the Landlord locust user, which svc-cargo answers
+        AssetsEvent::Vertiports(s) => {
+            let body = serde_json::to_string(&s).unwrap();
+            dispatcher
+                .send(&dispatcher.targets.cargo, "/cargo/vertiports", Method::POST, body, sim_time)
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, stub_service};
+
+    fn registration(registration_number: &str) -> AircraftRegistration {
+        AircraftRegistration {
+            manufacturer: "Boeing".to_string(),
+            model: "747".to_string(),
+            registration_number: registration_number.to_string(),
+            max_payload_kg: 100000.0,
+            max_range_km: 10000.0,
+            owner: "owner".to_string(),
+            serial_number: "12345".to_string(),
+            status: "Available".to_string(),
+            whitelist: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn only_accepted_registrations_join_the_fleet() {
+        let dispatcher = stub_service(&[("/assets/aircraft", 200, "")]);
+        register_aircraft(&registration("N00001"), &dispatcher, at(0))
+            .await
+            .unwrap();
+        assert_eq!(dispatcher.world.fleet_availability(at(0)), (1, 1));
+
+        let dispatcher = stub_service(&[("/assets/aircraft", 400, "")]);
+        register_aircraft(&registration("N00001"), &dispatcher, at(0))
+            .await
+            .unwrap();
+        assert_eq!(dispatcher.world.fleet_availability(at(0)), (0, 0));
+    }
+}
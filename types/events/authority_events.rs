@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use hyper::{Body, Response};
+use crate::event_types::Dispatcher;
+
+///////////////////////////////////////////////////////////////////////
+/// Civil Authority Events
+///////////////////////////////////////////////////////////////////////
+
+/// Decisions and restrictions issued by a civil aviation authority
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum AuthorityEvent {
+    /// Close the airspace over an area
+    Tfr(Tfr),
+
+    /// Shut down a vertiport
+    VertiportClosure(VertiportClosure),
+
+    /// Reopen a closed vertiport
+    VertiportReopening(VertiportReopening),
+
+    /// Approve or deny a flight plan
+    FlightPlanDecision(FlightPlanDecision),
+}
+
+/// A point on the map
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Coordinates {
+    /// Latitude (degrees)
+    pub latitude: f32,
+
+    /// Longitude (degrees)
+    pub longitude: f32,
+}
+
+/// Temporary flight restriction over a polygon
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tfr {
+    /// Identifier of the restriction
+    pub id: String,
+
+    /// Corners of the restricted area, in order
+    pub polygon: Vec<Coordinates>,
+
+    /// When the restriction begins (the event's timestamp if not provided)
+    #[serde(default)]
+    pub start: Option<NaiveDateTime>,
+
+    /// When the restriction ends (never if not provided)
+    #[serde(default)]
+    pub end: Option<NaiveDateTime>,
+
+    /// Why the airspace is closed
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A vertiport shutting down
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VertiportClosure {
+    /// The vertiport's ID
+    pub vertiport_id: String,
+
+    /// When the vertiport closes (the event's timestamp if not provided)
+    #[serde(default)]
+    pub start: Option<NaiveDateTime>,
+
+    /// When the vertiport reopens (on a reopening event if not provided)
+    #[serde(default)]
+    pub end: Option<NaiveDateTime>,
+
+    /// Why the vertiport is closed
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A closed vertiport reopening
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VertiportReopening {
+    /// The vertiport's ID
+    pub vertiport_id: String,
+}
+
+/// An authority's decision on a flight plan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlightPlanDecision {
+    /// The flight plan's ID
+    pub fp_id: String,
+
+    /// Whether the flight may go ahead
+    pub approved: bool,
+
+    /// Why the decision was made
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Whether a time falls within an optional window
+pub(crate) fn in_window(start: Option<NaiveDateTime>, end: Option<NaiveDateTime>, t: NaiveDateTime) -> bool {
+    !matches!(start, Some(start) if t < start) && !matches!(end, Some(end) if t >= end)
+}
+
+impl Tfr {
+    /// Whether the restriction is in effect at time `t`
+    pub fn is_active(&self, t: NaiveDateTime) -> bool {
+        in_window(self.start, self.end, t)
+    }
+
+    /// Whether a point lies within the restricted area
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        // Count crossings of a ray cast from the point
+        let mut inside = false;
+        let n = self.polygon.len();
+        for i in 0..n {
+            let a = self.polygon[i];
+            let b = self.polygon[(i + n - 1) % n];
+            if (a.latitude > latitude) != (b.latitude > latitude) {
+                let crossing = a.longitude
+                    + (latitude - a.latitude) / (b.latitude - a.latitude)
+                        * (b.longitude - a.longitude);
+                if longitude < crossing {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+}
+
+impl VertiportClosure {
+    /// Whether the vertiport is closed at time `t`
+    pub fn is_active(&self, t: NaiveDateTime) -> bool {
+        in_window(self.start, self.end, t)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////
+/// Actions on Authority Events
+///////////////////////////////////////////////////////////////////////
+
+/// Applies an authority decision to the simulated world and passes it
+///  on to the environment stand-in, if one is targeted
+/// # Arguments
+/// * event - The decision or restriction
+/// * dispatcher - Holds the simulated world and sends the request
+/// * sim_time - The simulation time at which the event occurs
+/// # Returns
+/// Result of the REST request
+pub async fn action(
+    event: &AuthorityEvent,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let world = &dispatcher.world;
+    let (path, body) = match event {
+        AuthorityEvent::Tfr(s) => {
+            let mut tfr = s.clone();
+            tfr.start = tfr.start.or(Some(sim_time));
+            world.add_tfr(tfr.clone());
+            ("/authority/tfr", serde_json::to_string(&tfr))
+        }
+        AuthorityEvent::VertiportClosure(s) => {
+            let mut closure = s.clone();
+            closure.start = closure.start.or(Some(sim_time));
+            world.close_vertiport(closure.clone());
+            ("/authority/vertiport_closure", serde_json::to_string(&closure))
+        }
+        AuthorityEvent::VertiportReopening(s) => {
+            world.reopen_vertiport(&s.vertiport_id, sim_time);
+            ("/authority/vertiport_reopening", serde_json::to_string(&s))
+        }
+        AuthorityEvent::FlightPlanDecision(s) => {
+            world.decide_flight_plan(s.clone());
+            ("/authority/flight_plan_decision", serde_json::to_string(&s))
+        }
+    };
+
+    dispatcher.publish(path, body.unwrap(), sim_time).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+
+    fn tfr(
+        corners: &[(f32, f32)],
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+    ) -> Tfr {
+        Tfr {
+            id: "tfr-1".to_string(),
+            polygon: corners
+                .iter()
+                .map(|&(latitude, longitude)| Coordinates {
+                    latitude,
+                    longitude,
+                })
+                .collect(),
+            start,
+            end,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn window_includes_start_and_excludes_end() {
+        assert!(in_window(None, None, at(0)));
+        assert!(!in_window(Some(at(10)), None, at(9)));
+        assert!(in_window(Some(at(10)), None, at(10)));
+        assert!(in_window(None, Some(at(20)), at(19)));
+        assert!(!in_window(None, Some(at(20)), at(20)));
+        assert!(in_window(Some(at(10)), Some(at(20)), at(15)));
+        assert!(!tfr(&[], Some(at(10)), Some(at(20))).is_active(at(25)));
+    }
+
+    #[test]
+    fn contains_points_inside_the_polygon() {
+        let square = tfr(
+            &[(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)],
+            None,
+            None,
+        );
+        assert!(square.contains(5.0, 5.0));
+        assert!(square.contains(0.5, 9.5));
+        assert!(!square.contains(-1.0, 5.0));
+        assert!(!square.contains(5.0, 11.0));
+
+        // An L shape, whose bounding box isn't all restricted
+        let l_shape = tfr(
+            &[
+                (0.0, 0.0),
+                (0.0, 10.0),
+                (5.0, 10.0),
+                (5.0, 5.0),
+                (10.0, 5.0),
+                (10.0, 0.0),
+            ],
+            None,
+            None,
+        );
+        assert!(l_shape.contains(2.0, 8.0));
+        assert!(l_shape.contains(8.0, 2.0));
+        assert!(!l_shape.contains(8.0, 8.0));
+    }
+
+    #[test]
+    fn degenerate_polygons_contain_nothing() {
+        assert!(!tfr(&[], None, None).contains(0.0, 0.0));
+        assert!(!tfr(&[(0.0, 0.0), (10.0, 10.0)], None, None).contains(5.0, 5.0));
+    }
+}
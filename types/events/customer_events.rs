@@ -1,7 +1,10 @@
 // use serde_yaml; // 0.8.23
 use serde::{Deserialize, Serialize};
-use hyper::{Body, Client, Method, Request, Response};
+use chrono::NaiveDateTime;
+use std::time::SystemTime;
+use hyper::{Body, Method, Response};
 pub use svc_cargo_client_rest::types as cargo_client_types;
+use crate::event_types::Dispatcher;
 
 ///////////////////////////////////////////////////////////////////////
 /// CustomerRequest Events
@@ -12,8 +15,12 @@ pub use svc_cargo_client_rest::types as cargo_client_types;
 pub enum CustomerEvent {
     /// Request for a new cargo flight
     CargoRequest(CargoRequest),
-    // Rideshare(RideshareRequest),
-    // Charter(CharterRequest),
+
+    /// Request for a seat on a shared passenger flight
+    Rideshare(PassengerRequest),
+
+    /// Request for a private flight
+    Charter(PassengerRequest),
 }
 
 /// Customer requests for cargo flights
@@ -31,95 +38,163 @@ pub enum CargoRequest {
     /// Cancel a cargo flight
     Cancel(cargo_client_types::FlightCancel),
 
-    // Modify(cargo_client_types::ModifyQuery) // R2
+    /// Change a confirmed cargo flight
+    Modify(FlightModify),
 }
 
-///////////////////////////////////////////////////////////////////////
-/// Actions on Customer Events
-///////////////////////////////////////////////////////////////////////
-fn get_server_addr() -> String {
-    "http://0.0.0.0:8000".into()
+/// Changes to a confirmed cargo flight
+///
+/// Fields that aren't provided are left as they are.
+///
+/// svc-cargo-client-rest doesn't define a modify request yet, so this is
+///  a placeholder until svc-cargo ships ModifyQuery.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlightModify {
+    /// The confirmed flight plan's ID
+    pub fp_id: String,
+
+    /// New weight of the cargo (kilograms)
+    #[serde(default)]
+    pub cargo_weight_kg: Option<f32>,
+
+    /// New earliest acceptable departure
+    #[serde(default)]
+    pub timestamp_depart_min: Option<SystemTime>,
+
+    /// New latest acceptable departure
+    #[serde(default)]
+    pub timestamp_depart_max: Option<SystemTime>,
+
+    /// New earliest acceptable arrival
+    #[serde(default)]
+    pub timestamp_arrive_min: Option<SystemTime>,
+
+    /// New latest acceptable arrival
+    #[serde(default)]
+    pub timestamp_arrive_max: Option<SystemTime>,
 }
 
-async fn cargo(event: &CargoRequest) -> Result<Response<Body>, ()> {
-    let url = format!("{}/cargo", get_server_addr());
-    let client = Client::builder()
-        .pool_idle_timeout(std::time::Duration::from_secs(10))
-        .build_http();
+/// Customer requests for rideshare or charter flights
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum PassengerRequest {
+    /// Request list of vertiports
+    Vertiports(cargo_client_types::VertiportsQuery),
+
+    /// Find seats on upcoming flights, or price a private flight
+    Query(PassengerQuery),
 
-    match event {
-        CargoRequest::Vertiports(s) => {
-            let endpoint = format!("{url}/vertiports");
-            let req = Request::builder()
-                .method(Method::POST)
-                .uri(endpoint)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&s).unwrap()))
-                .unwrap();
-            
-            match client.request(req).await {
-                Ok(r) => Ok(r),
-                Err(e) => {
-                    println!("Failed to request: {:?}", e);
-                    Err(())
-                }
-            }
-        }
-        CargoRequest::Query(s) => {
-            let endpoint = format!("{url}/query");
-            let req = Request::builder()
-                .method(Method::POST)
-                .uri(endpoint)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&s).unwrap()))
-                .unwrap();
-            
-            match client.request(req).await {
-                Ok(r) => Ok(r),
-                Err(e) => {
-                    println!("Failed to request: {:?}", e);
-                    Err(())
-                }
-            }
-        }
-        CargoRequest::Cancel(s) => {
-            let endpoint = format!("{url}/cancel");
-            let req = Request::builder()
-                .method(Method::DELETE)
-                .uri(endpoint)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&s).unwrap()))
-                .unwrap();
-
-            match client.request(req).await {
-                Ok(r) => Ok(r),
-                _ => Err(())
-            }
+    /// Confirm a flight
+    Confirm(cargo_client_types::FlightConfirm),
+
+    /// Cancel a flight
+    Cancel(cargo_client_types::FlightCancel),
+}
+
+/// Search for passenger flights between two vertiports
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PassengerQuery {
+    /// The departure vertiport's ID
+    pub vertiport_depart_id: String,
+
+    /// The arrival vertiport's ID
+    pub vertiport_arrive_id: String,
+
+    /// Earliest acceptable departure
+    pub timestamp_depart_min: Option<SystemTime>,
+
+    /// Latest acceptable departure
+    pub timestamp_depart_max: Option<SystemTime>,
+
+    /// Earliest acceptable arrival
+    pub timestamp_arrive_min: Option<SystemTime>,
+
+    /// Latest acceptable arrival
+    pub timestamp_arrive_max: Option<SystemTime>,
+
+    /// Number of seats needed
+    pub passengers: u32,
+
+    /// Weight of luggage and other cargo on a charter flight (kilograms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo_weight_kg: Option<f32>,
+}
+
+/// An HTTP method, the path of an endpoint within its service, and a
+///  JSON request body
+type Endpoint = (Method, &'static str, serde_json::Result<String>);
+
+impl CargoRequest {
+    fn endpoint(&self) -> Endpoint {
+        match self {
+            CargoRequest::Vertiports(s) => (Method::POST, "/vertiports", serde_json::to_string(&s)),
+            CargoRequest::Query(s) => (Method::POST, "/query", serde_json::to_string(&s)),
+            CargoRequest::Cancel(s) => (Method::DELETE, "/cancel", serde_json::to_string(&s)),
+            CargoRequest::Confirm(s) => (Method::PUT, "/confirm", serde_json::to_string(&s)),
+            CargoRequest::Modify(s) => (Method::PUT, "/modify", serde_json::to_string(&s)),
         }
-        CargoRequest::Confirm(s) => {
-            let endpoint = format!("{url}/confirm");
-            let req = Request::builder()
-                .method(Method::PUT)
-                .uri(endpoint)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&s).unwrap()))
-                .unwrap();
-
-            match client.request(req).await {
-                Ok(r) => Ok(r),
-                _ => Err(())
-            }
+    }
+}
+
+impl PassengerRequest {
+    fn endpoint(&self) -> Endpoint {
+        match self {
+            PassengerRequest::Vertiports(s) => (Method::POST, "/vertiports", serde_json::to_string(&s)),
+            PassengerRequest::Query(s) => (Method::POST, "/query", serde_json::to_string(&s)),
+            PassengerRequest::Cancel(s) => (Method::DELETE, "/cancel", serde_json::to_string(&s)),
+            PassengerRequest::Confirm(s) => (Method::PUT, "/confirm", serde_json::to_string(&s)),
         }
     }
 }
 
+///////////////////////////////////////////////////////////////////////
+/// Actions on Customer Events
+///////////////////////////////////////////////////////////////////////
+
+/// Sends a request to one of the customer-facing services
+/// # Arguments
+/// * service - The service's path prefix, e.g. `/cargo`
+/// * base_url - Base URL of the service
+/// * endpoint - The request to send
+/// * dispatcher - Sends the request and records the outcome
+/// * sim_time - The simulation time at which the event occurs
+async fn send(
+    service: &str,
+    base_url: &str,
+    (method, path, body): Endpoint,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let body = body.map_err(|e| {
+        println!("Could not serialize request: {:?}", e);
+    })?;
+
+    dispatcher
+        .send(base_url, &format!("{service}{path}"), method, body, sim_time)
+        .await
+}
+
 /// Mimics a REST message from an external client
 /// # Arguments
 /// * event - The type of event to mimic
+/// * dispatcher - Sends the request and records the outcome
+/// * sim_time - The simulation time at which the event occurs
 /// # Returns
 /// Result of the REST request
-pub async fn action(event: &CustomerEvent) -> Result<Response<Body>, ()> {
+pub async fn action(
+    event: &CustomerEvent,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let targets = &dispatcher.targets;
     match event {
-        CustomerEvent::CargoRequest(s) => cargo(s).await,
+        CustomerEvent::CargoRequest(s) => {
+            send("/cargo", &targets.cargo, s.endpoint(), dispatcher, sim_time).await
+        }
+        CustomerEvent::Rideshare(s) => {
+            send("/rideshare", &targets.rideshare, s.endpoint(), dispatcher, sim_time).await
+        }
+        CustomerEvent::Charter(s) => {
+            send("/charter", &targets.charter, s.endpoint(), dispatcher, sim_time).await
+        }
     }
 }
@@ -1,2 +1,311 @@
+use crate::eel_types::{EelEventType, Recorder};
+use crate::metrics_types::{Metrics, RequestRecord};
+use chrono::{NaiveDateTime, Utc};
+use hyper::body::HttpBody;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request, Response, Uri};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use world::World;
+
 /// Customer-driven events (booking, cancelling, etc.)
 pub mod customer_events;
+
+/// Weather conditions that affect flights and customers
+pub mod weather_events;
+
+/// Restrictions and decisions from civil aviation authorities
+pub mod authority_events;
+
+/// Changes to the fleet of aircraft
+pub mod aircraft_events;
+
+/// Requests from fleet and vertiport operators
+pub mod assets_events;
+
+/// Shared state of the simulated world
+pub mod world;
+
+/// Base URLs of the services under test
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Targets {
+    /// svc-cargo REST server
+    pub cargo: String,
+
+    /// svc-rideshare REST server
+    pub rideshare: String,
+
+    /// svc-charter REST server
+    pub charter: String,
+
+    /// svc-assets REST server
+    pub assets: String,
+
+    /// REST stand-in that receives weather, authority and other environment events
+    ///  (events are only applied within the simulation if not provided)
+    pub environment: Option<String>,
+}
+
+impl Default for Targets {
+    fn default() -> Self {
+        Targets {
+            cargo: "http://0.0.0.0:8000".into(),
+            rideshare: "http://0.0.0.0:8001".into(),
+            charter: "http://0.0.0.0:8002".into(),
+            assets: "http://0.0.0.0:8004".into(),
+            environment: None,
+        }
+    }
+}
+
+impl Targets {
+    /// Checks that every base URL has a scheme and a host
+    pub fn validate(&self) -> Result<(), String> {
+        let mut urls = vec![
+            ("cargo", &self.cargo),
+            ("rideshare", &self.rideshare),
+            ("charter", &self.charter),
+            ("assets", &self.assets),
+        ];
+
+        if let Some(url) = &self.environment {
+            urls.push(("environment", url));
+        }
+
+        for (name, url) in urls {
+            match url.parse::<Uri>() {
+                Ok(uri) if uri.scheme().is_some() && uri.authority().is_some() => {}
+                _ => return Err(format!("{name} target '{url}' is not a valid base URL")),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Header carrying the simulated time at which a request was sent
+pub const SIM_TIME_HEADER: &str = "x-sim-time";
+
+/// Sends requests to the services under test and records how they went
+///
+/// Clones share the same HTTP connection pool and metrics.
+#[derive(Debug, Clone)]
+pub struct Dispatcher {
+    /// Services to send requests to
+    pub targets: Targets,
+
+    /// Where the outcome of every request is recorded
+    pub metrics: Metrics,
+
+    /// Where dispatched events are captured for replay, if anywhere
+    pub recorder: Option<Recorder>,
+
+    /// Conditions in the simulated world
+    pub world: World,
+
+    client: Client<HttpConnector>,
+}
+
+impl Dispatcher {
+    /// Creates a dispatcher sending requests to `targets`
+    pub fn new(targets: Targets, metrics: Metrics) -> Self {
+        let client = Client::builder()
+            .pool_idle_timeout(std::time::Duration::from_secs(10))
+            .build_http();
+
+        Dispatcher {
+            targets,
+            metrics,
+            recorder: None,
+            world: World::new(),
+            client,
+        }
+    }
+
+    /// Captures every event sent through this dispatcher
+    pub fn with_recorder(mut self, recorder: Option<Recorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// Records an event for replay, if recording, and carries it out
+    /// # Arguments
+    /// * event - The event to carry out
+    /// * sim_time - The simulation time at which the event occurs
+    /// # Returns
+    /// Result of the REST request
+    pub async fn dispatch(
+        &self,
+        event: &EelEventType,
+        sim_time: NaiveDateTime,
+    ) -> Result<Response<Body>, ()> {
+        let recorded = self
+            .recorder
+            .as_ref()
+            .map(|recorder| (recorder, recorder.record(event.clone(), sim_time)));
+
+        let result = match event {
+            EelEventType::CustomerEvent(e) => customer_events::action(e, self, sim_time).await,
+            EelEventType::WeatherEvent(e) => weather_events::action(e, self, sim_time).await,
+            EelEventType::AuthorityEvent(e) => authority_events::action(e, self, sim_time).await,
+            EelEventType::AircraftEvent(e) => aircraft_events::action(e, self, sim_time).await,
+            EelEventType::AssetsEvent(e) => assets_events::action(e, self, sim_time).await,
+        };
+
+        let (recorder, index) = match recorded {
+            Some(recorded) => recorded,
+            None => return result,
+        };
+
+        let response = match result {
+            Ok(r) if r.status().is_success() => r,
+            other => return other,
+        };
+
+        // The recorder captures identifiers from successful responses
+        let (parts, body) = response.into_parts();
+        let bytes = hyper::body::to_bytes(body).await.map_err(|e| {
+            println!("Failed to read response: {:?}", e);
+        })?;
+
+        recorder.respond(index, &bytes);
+        Ok(Response::from_parts(parts, Body::from(bytes)))
+    }
+
+    /// Passes an environment event on to the environment stand-in
+    ///
+    /// If no stand-in is targeted, the event only affects the simulated
+    ///  world and an empty `200 OK` response is returned.
+    /// # Arguments
+    /// * path - Path of the endpoint, e.g. `/weather`
+    /// * body - JSON request body
+    /// * sim_time - Simulation time at which the event occurs
+    pub async fn publish(
+        &self,
+        path: &str,
+        body: String,
+        sim_time: NaiveDateTime,
+    ) -> Result<Response<Body>, ()> {
+        match &self.targets.environment {
+            Some(url) => self.send(url, path, Method::POST, body, sim_time).await,
+            None => Ok(Response::new(Body::empty())),
+        }
+    }
+
+    /// Sends a JSON request and records its outcome
+    ///
+    /// The response body is read in full so that its size and the
+    ///  complete latency can be recorded. The simulated time is sent
+    ///  in the [`SIM_TIME_HEADER`] header.
+    /// # Arguments
+    /// * base_url - Base URL of the service, from [`Targets`]
+    /// * path - Path of the endpoint, e.g. `/cargo/query`
+    /// * method - HTTP method
+    /// * body - JSON request body
+    /// * sim_time - Simulation time at which the request is sent
+    pub async fn send(
+        &self,
+        base_url: &str,
+        path: &str,
+        method: Method,
+        body: String,
+        sim_time: NaiveDateTime,
+    ) -> Result<Response<Body>, ()> {
+        let req = json_request(base_url, path, method.clone(), body, sim_time).map_err(|e| {
+            println!("Failed to build request for {base_url}{path}: {:?}", e);
+        })?;
+
+        let wall_time = Utc::now();
+        let started = Instant::now();
+        let result = match self.client.request(req).await {
+            Ok(r) => {
+                let (parts, body) = r.into_parts();
+                hyper::body::to_bytes(body)
+                    .await
+                    .map(|bytes| Response::from_parts(parts, Body::from(bytes)))
+            }
+            Err(e) => Err(e),
+        };
+
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let (status, response_bytes) = match &result {
+            Ok(r) => (
+                Some(r.status().as_u16()),
+                r.body().size_hint().exact().unwrap_or(0) as usize,
+            ),
+            Err(_) => (None, 0),
+        };
+
+        self.metrics.record(RequestRecord {
+            endpoint: path.to_string(),
+            method: method.to_string(),
+            status,
+            latency_ms,
+            response_bytes,
+            sim_time,
+            wall_time,
+        });
+
+        result.map_err(|e| {
+            println!("Failed to request: {:?}", e);
+        })
+    }
+}
+
+/// Builds a JSON request stamped with its simulated time
+/// # Arguments
+/// * base_url - Base URL of the service, from [`Targets`]
+/// * path - Path of the endpoint, e.g. `/cargo/query`
+/// * method - HTTP method
+/// * body - JSON request body
+/// * sim_time - Simulation time at which the request is sent
+fn json_request(
+    base_url: &str,
+    path: &str,
+    method: Method,
+    body: String,
+    sim_time: NaiveDateTime,
+) -> hyper::http::Result<Request<Body>> {
+    Request::builder()
+        .method(method)
+        .uri(format!("{base_url}{path}"))
+        .header("content-type", "application/json")
+        .header(
+            SIM_TIME_HEADER,
+            sim_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        )
+        .body(Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+
+    #[test]
+    fn requests_carry_their_simulated_time() {
+        let req = json_request(
+            "http://localhost",
+            "/cargo/query",
+            Method::POST,
+            "{}".into(),
+            at(65),
+        )
+        .unwrap();
+        assert_eq!(req.uri(), "http://localhost/cargo/query");
+        assert_eq!(req.headers()[SIM_TIME_HEADER], "2022-01-01T00:01:05");
+    }
+
+    #[test]
+    fn bad_urls_are_errors() {
+        let req = json_request(
+            "not a url",
+            "/cargo/query",
+            Method::POST,
+            "{}".into(),
+            at(0),
+        );
+        assert!(req.is_err());
+    }
+}
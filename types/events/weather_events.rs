@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use hyper::{Body, Response};
+use crate::event_types::Dispatcher;
+use crate::event_types::world::Region;
+
+///////////////////////////////////////////////////////////////////////
+/// Weather Events
+///////////////////////////////////////////////////////////////////////
+
+/// Wind speed (knots) at or above which conditions are adverse
+pub const ADVERSE_WIND_KTS: f32 = 25.0;
+
+/// Visibility (meters) below which conditions are adverse
+pub const ADVERSE_VISIBILITY_M: f32 = 1600.0;
+
+/// Kind and intensity of precipitation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Precipitation {
+    /// Dry
+    #[default]
+    None,
+
+    /// Drizzle or light rain or snow
+    Light,
+
+    /// Steady rain or snow
+    Moderate,
+
+    /// Heavy rain, snow or hail
+    Heavy,
+
+    /// Thunderstorms
+    Thunderstorm,
+}
+
+/// Weather conditions over part of the simulated world
+///
+/// Applies to the listed vertiports and everything within `region`, or
+///  everywhere if neither is provided. A later event for the same place
+///  replaces an earlier one.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeatherEvent {
+    /// Sustained wind speed (knots)
+    #[serde(default)]
+    pub wind_speed_kts: f32,
+
+    /// Direction the wind blows from (degrees)
+    #[serde(default)]
+    pub wind_direction_deg: f32,
+
+    /// Peak gust speed (knots)
+    #[serde(default)]
+    pub gust_kts: Option<f32>,
+
+    /// Horizontal visibility (meters, unlimited if not provided)
+    #[serde(default)]
+    pub visibility_m: Option<f32>,
+
+    /// Precipitation
+    #[serde(default)]
+    pub precipitation: Precipitation,
+
+    /// IDs of affected vertiports
+    #[serde(default)]
+    pub vertiports: Vec<String>,
+
+    /// Affected area
+    #[serde(default)]
+    pub region: Option<Region>,
+
+    /// When the conditions begin (the event's timestamp if not provided)
+    #[serde(default)]
+    pub start: Option<NaiveDateTime>,
+
+    /// When the conditions end (until replaced if not provided)
+    #[serde(default)]
+    pub end: Option<NaiveDateTime>,
+}
+
+impl WeatherEvent {
+    /// Whether the conditions are bad enough to disrupt flights
+    pub fn is_adverse(&self) -> bool {
+        let wind = self.gust_kts.unwrap_or(0.0).max(self.wind_speed_kts);
+        wind >= ADVERSE_WIND_KTS
+            || matches!(self.visibility_m, Some(v) if v < ADVERSE_VISIBILITY_M)
+            || matches!(
+                self.precipitation,
+                Precipitation::Heavy | Precipitation::Thunderstorm
+            )
+    }
+
+    /// Whether the conditions are in effect at time `t`
+    pub fn is_active(&self, t: NaiveDateTime) -> bool {
+        !matches!(self.start, Some(start) if t < start) && !matches!(self.end, Some(end) if t >= end)
+    }
+
+    /// Whether the conditions cover a vertiport
+    /// # Arguments
+    /// * id - The vertiport's ID
+    /// * latitude - The vertiport's latitude (degrees)
+    /// * longitude - The vertiport's longitude (degrees)
+    pub fn covers(&self, id: &str, latitude: f32, longitude: f32) -> bool {
+        if self.vertiports.is_empty() && self.region.is_none() {
+            return true;
+        }
+
+        self.vertiports.iter().any(|v| v == id)
+            || matches!(&self.region, Some(r) if r.contains(latitude, longitude))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////
+/// Actions on Weather Events
+///////////////////////////////////////////////////////////////////////
+
+/// Applies weather to the simulated world and passes it on to the
+///  environment stand-in, if one is targeted
+/// # Arguments
+/// * event - The weather conditions
+/// * dispatcher - Holds the simulated world and sends the request
+/// * sim_time - The simulation time at which the event occurs
+/// # Returns
+/// Result of the REST request
+pub async fn action(
+    event: &WeatherEvent,
+    dispatcher: &Dispatcher,
+    sim_time: NaiveDateTime
+) -> Result<Response<Body>, ()> {
+    let mut event = event.clone();
+    event.start = event.start.or(Some(sim_time));
+    dispatcher.world.add_weather(event.clone());
+
+    let body = serde_json::to_string(&event).unwrap();
+    dispatcher.publish("/weather", body, sim_time).await
+}
@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use crate::event_types::aircraft_events::{AircraftRegistration, OutOfService};
+use crate::event_types::authority_events::{FlightPlanDecision, Tfr, VertiportClosure};
+use crate::event_types::weather_events::WeatherEvent;
+
+/// Mean radius of the earth (kilometers)
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A circular area on the map
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Region {
+    /// Latitude of the center (degrees)
+    pub latitude: f32,
+
+    /// Longitude of the center (degrees)
+    pub longitude: f32,
+
+    /// Distance from the center to the edge (kilometers)
+    pub radius_km: f32,
+}
+
+/// Great-circle distance between two points (kilometers)
+pub fn distance_km(a: (f32, f32), b: (f32, f32)) -> f64 {
+    let (lat_a, lon_a) = (f64::from(a.0).to_radians(), f64::from(a.1).to_radians());
+    let (lat_b, lon_b) = (f64::from(b.0).to_radians(), f64::from(b.1).to_radians());
+    let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+impl Region {
+    /// Whether a point lies within the region
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        distance_km((self.latitude, self.longitude), (latitude, longitude))
+            <= f64::from(self.radius_km)
+    }
+}
+
+/// What is known about an aircraft in the fleet
+#[derive(Debug, Clone)]
+pub struct AircraftState {
+    /// The aircraft's details, if it was registered during the run
+    pub registration: Option<AircraftRegistration>,
+
+    /// Times the aircraft is out of service
+    pub outages: Vec<OutOfService>,
+
+    /// Charge the battery can hold, as a percentage of when new
+    ///  (reported to the environment stand-in, flights aren't limited by it)
+    pub battery_capacity_percent: f32,
+
+    /// When a delayed aircraft is expected to arrive
+    pub delayed_until: Option<NaiveDateTime>,
+
+    /// The vertiport the aircraft is at or headed to, if known
+    pub location: Option<String>,
+}
+
+impl Default for AircraftState {
+    fn default() -> Self {
+        AircraftState {
+            registration: None,
+            outages: vec![],
+            battery_capacity_percent: 100.0,
+            delayed_until: None,
+            location: None,
+        }
+    }
+}
+
+impl AircraftState {
+    /// Whether the aircraft can fly at time `t`
+    pub fn is_available(&self, t: NaiveDateTime) -> bool {
+        !self.outages.iter().any(|o| o.is_active(t))
+            && !matches!(self.delayed_until, Some(until) if t < until)
+    }
+}
+
+/// Conditions in the simulated world, as set by EEL events
+#[derive(Debug, Default, Clone)]
+pub struct WorldState {
+    /// Weather events, in the order they occurred
+    pub weather: Vec<WeatherEvent>,
+
+    /// Temporary flight restrictions
+    pub tfrs: Vec<Tfr>,
+
+    /// Vertiport closures
+    pub closures: Vec<VertiportClosure>,
+
+    /// Authority decisions on flight plans, by flight plan ID
+    pub decisions: BTreeMap<String, FlightPlanDecision>,
+
+    /// Aircraft that events have referred to, by ID
+    pub fleet: BTreeMap<String, AircraftState>,
+}
+
+/// The simulated world shared by every agent in a run
+///
+/// Clones share the same underlying state.
+#[derive(Debug, Default, Clone)]
+pub struct World {
+    state: Arc<Mutex<WorldState>>,
+}
+
+impl World {
+    /// Creates a world with calm weather and no restrictions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the current conditions
+    pub fn state(&self) -> WorldState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Adds weather conditions
+    pub fn add_weather(&self, event: WeatherEvent) {
+        self.state.lock().unwrap().weather.push(event);
+    }
+
+    /// The weather at a vertiport at time `t` (`None` if calm)
+    ///
+    /// The most recent event covering the vertiport wins.
+    /// # Arguments
+    /// * id - The vertiport's ID
+    /// * latitude - The vertiport's latitude (degrees)
+    /// * longitude - The vertiport's longitude (degrees)
+    /// * t - The simulation time
+    pub fn weather_at(
+        &self,
+        id: &str,
+        latitude: f32,
+        longitude: f32,
+        t: NaiveDateTime
+    ) -> Option<WeatherEvent> {
+        self.state
+            .lock()
+            .unwrap()
+            .weather
+            .iter()
+            .rev()
+            .find(|w| w.is_active(t) && w.covers(id, latitude, longitude))
+            .cloned()
+    }
+
+    /// Adds a temporary flight restriction
+    pub fn add_tfr(&self, tfr: Tfr) {
+        self.state.lock().unwrap().tfrs.push(tfr);
+    }
+
+    /// Closes a vertiport
+    pub fn close_vertiport(&self, closure: VertiportClosure) {
+        self.state.lock().unwrap().closures.push(closure);
+    }
+
+    /// Ends every open-ended closure of a vertiport at time `t`
+    pub fn reopen_vertiport(&self, id: &str, t: NaiveDateTime) {
+        let mut state = self.state.lock().unwrap();
+        for c in state.closures.iter_mut() {
+            if c.vertiport_id == id && c.is_active(t) {
+                c.end = Some(t);
+            }
+        }
+    }
+
+    /// Records an authority's decision on a flight plan
+    pub fn decide_flight_plan(&self, decision: FlightPlanDecision) {
+        self.state
+            .lock()
+            .unwrap()
+            .decisions
+            .insert(decision.fp_id.clone(), decision);
+    }
+
+    /// The authority's decision on a flight plan, if one was made
+    pub fn flight_plan_decision(&self, fp_id: &str) -> Option<FlightPlanDecision> {
+        self.state.lock().unwrap().decisions.get(fp_id).cloned()
+    }
+
+    /// Why a vertiport can't be used at time `t` (`None` if it can)
+    /// # Arguments
+    /// * id - The vertiport's ID
+    /// * latitude - The vertiport's latitude (degrees)
+    /// * longitude - The vertiport's longitude (degrees)
+    /// * t - The simulation time
+    pub fn restriction(
+        &self,
+        id: &str,
+        latitude: f32,
+        longitude: f32,
+        t: NaiveDateTime
+    ) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        if let Some(c) = state
+            .closures
+            .iter()
+            .find(|c| c.vertiport_id == id && c.is_active(t))
+        {
+            return Some(match &c.reason {
+                Some(reason) => format!("{id} is closed ({reason})"),
+                None => format!("{id} is closed"),
+            });
+        }
+
+        state
+            .tfrs
+            .iter()
+            .find(|tfr| tfr.is_active(t) && tfr.contains(latitude, longitude))
+            .map(|tfr| format!("{id} is under flight restriction {}", tfr.id))
+    }
+
+    /// Adds an aircraft to the fleet
+    pub fn register_aircraft(&self, registration: AircraftRegistration) {
+        let id = registration.registration_number.clone();
+        self.state
+            .lock()
+            .unwrap()
+            .fleet
+            .entry(id)
+            .or_default()
+            .registration = Some(registration);
+    }
+
+    /// Takes an aircraft out of service
+    pub fn take_out_of_service(&self, outage: OutOfService) {
+        let mut state = self.state.lock().unwrap();
+        let aircraft = state.fleet.entry(outage.aircraft_id.clone()).or_default();
+        if outage.vertiport_id.is_some() {
+            aircraft.location = outage.vertiport_id.clone();
+        }
+
+        aircraft.outages.push(outage);
+    }
+
+    /// Ends every open-ended outage of an aircraft at time `t`
+    pub fn return_to_service(&self, id: &str, t: NaiveDateTime) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(aircraft) = state.fleet.get_mut(id) {
+            for o in aircraft.outages.iter_mut() {
+                if o.is_active(t) {
+                    o.end = Some(t);
+                }
+            }
+        }
+    }
+
+    /// Sets how much charge an aircraft's battery can hold
+    /// # Arguments
+    /// * id - The aircraft's ID
+    /// * capacity_percent - Percentage of the capacity when new (0 to 100)
+    pub fn degrade_battery(&self, id: &str, capacity_percent: f32) {
+        self.state
+            .lock()
+            .unwrap()
+            .fleet
+            .entry(id.to_string())
+            .or_default()
+            .battery_capacity_percent = capacity_percent.clamp(0.0, 100.0);
+    }
+
+    /// Holds up an aircraft until time `until`
+    /// # Arguments
+    /// * id - The aircraft's ID
+    /// * vertiport_id - The vertiport the aircraft is headed to, if known
+    /// * until - When the aircraft arrives
+    pub fn delay_aircraft(&self, id: &str, vertiport_id: Option<String>, until: NaiveDateTime) {
+        let mut state = self.state.lock().unwrap();
+        let aircraft = state.fleet.entry(id.to_string()).or_default();
+        aircraft.delayed_until = Some(until);
+        if vertiport_id.is_some() {
+            aircraft.location = vertiport_id;
+        }
+    }
+
+    /// Whether an aircraft can fly from a vertiport at time `t`
+    ///
+    /// Events only reveal part of the fleet, so the services are trusted
+    ///  to only offer flights they have aircraft for unless every aircraft
+    ///  known to be at the vertiport is grounded or delayed.
+    pub fn has_aircraft_at(&self, vertiport_id: &str, t: NaiveDateTime) -> bool {
+        let state = self.state.lock().unwrap();
+        let mut at_vertiport = state
+            .fleet
+            .values()
+            .filter(|a| a.location.as_deref() == Some(vertiport_id))
+            .peekable();
+
+        at_vertiport.peek().is_none() || at_vertiport.any(|a| a.is_available(t))
+    }
+
+    /// The number of aircraft in the fleet and how many can fly at time `t`
+    pub fn fleet_availability(&self, t: NaiveDateTime) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        let available = state.fleet.values().filter(|a| a.is_available(t)).count();
+        (state.fleet.len(), available)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+
+    fn outage(
+        aircraft_id: &str,
+        vertiport_id: Option<&str>,
+        end: Option<NaiveDateTime>,
+    ) -> OutOfService {
+        OutOfService {
+            aircraft_id: aircraft_id.to_string(),
+            start: Some(at(0)),
+            end,
+            reason: None,
+            vertiport_id: vertiport_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn unknown_fleet_flies_from_anywhere() {
+        let world = World::new();
+        assert!(world.has_aircraft_at("vertiport-1", at(0)));
+    }
+
+    #[test]
+    fn grounded_aircraft_only_ground_their_vertiport() {
+        let world = World::new();
+        world.take_out_of_service(outage("N00001", None, Some(at(60))));
+        assert!(world.has_aircraft_at("vertiport-1", at(30)));
+
+        world.take_out_of_service(outage("N00002", Some("vertiport-1"), None));
+        assert!(!world.has_aircraft_at("vertiport-1", at(30)));
+        assert!(world.has_aircraft_at("vertiport-2", at(30)));
+        assert_eq!(world.fleet_availability(at(30)), (2, 0));
+
+        world.return_to_service("N00002", at(30));
+        assert!(world.has_aircraft_at("vertiport-1", at(30)));
+        assert_eq!(world.fleet_availability(at(30)), (2, 1));
+    }
+
+    #[test]
+    fn delayed_aircraft_only_hold_up_their_destination() {
+        let world = World::new();
+        world.delay_aircraft("N00001", Some("vertiport-2".to_string()), at(60));
+        assert!(!world.has_aircraft_at("vertiport-2", at(30)));
+        assert!(world.has_aircraft_at("vertiport-2", at(60)));
+        assert!(world.has_aircraft_at("vertiport-1", at(30)));
+        assert!(world.has_aircraft_at("vertiport-3", at(30)));
+
+        // Another aircraft waiting there can take the flight instead
+        world.delay_aircraft("N00002", Some("vertiport-2".to_string()), at(0));
+        assert!(world.has_aircraft_at("vertiport-2", at(30)));
+    }
+}
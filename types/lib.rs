@@ -14,3 +14,14 @@ pub mod cfg_types {
 pub mod event_types {
     include!("events/types.rs");
 }
+
+/// Types used to measure requests made during simulations
+pub mod metrics_types {
+    include!("metrics/types.rs");
+}
+
+/// Fixtures shared by the tests of this and the dependent crates
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util {
+    include!("test_util.rs");
+}
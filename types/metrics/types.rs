@@ -0,0 +1,405 @@
+use crate::cfg_types::customer_agent::CustomerStatus;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds of the request latency histogram buckets (seconds)
+const LATENCY_BUCKETS_S: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Outcome of a single request sent to a service
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RequestRecord {
+    /// Path of the endpoint, e.g. `/cargo/query`
+    pub endpoint: String,
+
+    /// HTTP method
+    pub method: String,
+
+    /// HTTP status code (`None` if no response was received)
+    pub status: Option<u16>,
+
+    /// Time from sending the request to receiving the whole body (milliseconds)
+    pub latency_ms: f64,
+
+    /// Size of the response body in bytes
+    pub response_bytes: usize,
+
+    /// Simulation time at which the request was sent
+    pub sim_time: NaiveDateTime,
+
+    /// Wall-clock time at which the request was sent
+    pub wall_time: DateTime<Utc>,
+}
+
+impl RequestRecord {
+    /// Whether the request failed or the service returned an error
+    pub fn is_error(&self) -> bool {
+        !matches!(self.status, Some(s) if s < 400)
+    }
+}
+
+/// Latency and error statistics for one endpoint
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EndpointSummary {
+    /// Path of the endpoint, e.g. `/cargo/query`
+    pub endpoint: String,
+
+    /// HTTP method
+    pub method: String,
+
+    /// Number of requests sent
+    pub count: usize,
+
+    /// Number of requests that failed or returned an error status
+    pub errors: usize,
+
+    /// Fraction of requests that were errors (0.0 to 1.0)
+    pub error_rate: f64,
+
+    /// Median latency (milliseconds)
+    pub p50_ms: f64,
+
+    /// 90th percentile latency (milliseconds)
+    pub p90_ms: f64,
+
+    /// 95th percentile latency (milliseconds)
+    pub p95_ms: f64,
+
+    /// 99th percentile latency (milliseconds)
+    pub p99_ms: f64,
+
+    /// Slowest request (milliseconds)
+    pub max_ms: f64,
+}
+
+/// Nearest-rank percentile of a sorted list
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl EndpointSummary {
+    /// Summarizes requests to a single endpoint
+    fn from_records(endpoint: &str, method: &str, records: &[&RequestRecord]) -> Self {
+        let mut latencies: Vec<f64> = records.iter().map(|r| r.latency_ms).collect();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let count = records.len();
+        let errors = records.iter().filter(|r| r.is_error()).count();
+        EndpointSummary {
+            endpoint: endpoint.to_string(),
+            method: method.to_string(),
+            count,
+            errors,
+            error_rate: if count > 0 {
+                errors as f64 / count as f64
+            } else {
+                0.0
+            },
+            p50_ms: percentile(&latencies, 50.0),
+            p90_ms: percentile(&latencies, 90.0),
+            p95_ms: percentile(&latencies, 95.0),
+            p99_ms: percentile(&latencies, 99.0),
+            max_ms: latencies.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+/// Latency histogram of one endpoint
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Requests at or below each of `LATENCY_BUCKETS_S`
+    buckets: [u64; LATENCY_BUCKETS_S.len()],
+
+    /// Total latency (seconds)
+    sum: f64,
+
+    /// Number of requests
+    count: u64,
+}
+
+/// Running totals exported to Prometheus, kept up to date as requests
+///  are recorded so a scrape doesn't have to walk every record
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    /// Requests by endpoint, method and status
+    requests: BTreeMap<(String, String, String), u64>,
+
+    /// Failed requests by endpoint and method
+    errors: BTreeMap<(String, String), u64>,
+
+    /// Latencies by endpoint and method
+    latencies: BTreeMap<(String, String), Histogram>,
+}
+
+impl Counters {
+    fn add(&mut self, r: &RequestRecord) {
+        let key = (r.endpoint.clone(), r.method.clone());
+        let status = r
+            .status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "none".to_string());
+        *self
+            .requests
+            .entry((key.0.clone(), key.1.clone(), status))
+            .or_default() += 1;
+
+        let errors = self.errors.entry(key.clone()).or_default();
+        if r.is_error() {
+            *errors += 1;
+        }
+
+        let latency_s = r.latency_ms / 1000.0;
+        let histogram = self.latencies.entry(key).or_default();
+        for (bucket, le) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS_S) {
+            if latency_s <= le {
+                *bucket += 1;
+            }
+        }
+
+        histogram.sum += latency_s;
+        histogram.count += 1;
+    }
+}
+
+/// Collects a record of every request made during a simulation,
+///  and how many customers are in each phase
+///
+/// Clones share the same underlying records.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    records: Arc<Mutex<Vec<RequestRecord>>>,
+    counters: Arc<Mutex<Counters>>,
+    customers: Arc<Mutex<BTreeMap<CustomerStatus, u64>>>,
+    customers_spawned: Arc<Mutex<u64>>,
+}
+
+impl Metrics {
+    /// Creates an empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a request to the collection
+    pub fn record(&self, record: RequestRecord) {
+        self.counters.lock().unwrap().add(&record);
+        self.records.lock().unwrap().push(record);
+    }
+
+    /// Tracks a customer moving between phases
+    /// # Arguments
+    /// * from - The phase the customer left (`None` for a new customer)
+    /// * to - The phase the customer entered
+    pub fn customer_moved(&self, from: Option<CustomerStatus>, to: CustomerStatus) {
+        let mut customers = self.customers.lock().unwrap();
+        match from {
+            Some(from) => {
+                if let Some(count) = customers.get_mut(&from) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+            None => *self.customers_spawned.lock().unwrap() += 1,
+        }
+
+        *customers.entry(to).or_default() += 1;
+    }
+
+    /// Tracks a customer leaving the simulation without finishing,
+    ///  e.g. because the simulation ended
+    /// # Arguments
+    /// * from - The phase the customer was in
+    pub fn customer_left(&self, from: CustomerStatus) {
+        if let Some(count) = self.customers.lock().unwrap().get_mut(&from) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    /// A copy of every request recorded so far
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Statistics for each endpoint, ordered by endpoint and method
+    pub fn summary(&self) -> Vec<EndpointSummary> {
+        let records = self.records.lock().unwrap();
+        let mut by_endpoint: BTreeMap<(&str, &str), Vec<&RequestRecord>> = BTreeMap::new();
+        for r in records.iter() {
+            by_endpoint
+                .entry((r.endpoint.as_str(), r.method.as_str()))
+                .or_default()
+                .push(r);
+        }
+
+        by_endpoint
+            .iter()
+            .map(|((endpoint, method), records)| {
+                EndpointSummary::from_records(endpoint, method, records)
+            })
+            .collect()
+    }
+
+    /// Prints a table of per-endpoint statistics to stdout
+    pub fn print_summary(&self) {
+        let summary = self.summary();
+        if summary.is_empty() {
+            println!("No requests recorded.");
+            return;
+        }
+
+        println!(
+            "{:<24} {:<7} {:>7} {:>7} {:>9} {:>9} {:>9} {:>9}",
+            "ENDPOINT", "METHOD", "COUNT", "ERRORS", "P50 MS", "P90 MS", "P99 MS", "MAX MS"
+        );
+
+        for s in summary {
+            println!(
+                "{:<24} {:<7} {:>7} {:>6.1}% {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+                s.endpoint,
+                s.method,
+                s.count,
+                100.0 * s.error_rate,
+                s.p50_ms,
+                s.p90_ms,
+                s.p99_ms,
+                s.max_ms
+            );
+        }
+    }
+
+    /// Renders the current state in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        // Writing to a String can't fail
+        let _ = self.write_prometheus(&mut out);
+        out
+    }
+
+    fn write_prometheus(&self, out: &mut String) -> std::fmt::Result {
+        // Copy the running totals so the lock isn't held while formatting
+        let counters = self.counters.lock().unwrap().clone();
+
+        writeln!(
+            out,
+            "# HELP sim_requests_total Requests sent to the services under test"
+        )?;
+        writeln!(out, "# TYPE sim_requests_total counter")?;
+        for ((endpoint, method, status), count) in &counters.requests {
+            writeln!(
+                out,
+                "sim_requests_total{{endpoint=\"{endpoint}\",method=\"{method}\",status=\"{status}\"}} {count}"
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP sim_request_errors_total Requests that failed or returned an error status"
+        )?;
+        writeln!(out, "# TYPE sim_request_errors_total counter")?;
+        for ((endpoint, method), count) in &counters.errors {
+            writeln!(
+                out,
+                "sim_request_errors_total{{endpoint=\"{endpoint}\",method=\"{method}\"}} {count}"
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP sim_request_duration_seconds Time from sending a request to receiving the whole response"
+        )?;
+        writeln!(out, "# TYPE sim_request_duration_seconds histogram")?;
+        for ((endpoint, method), histogram) in &counters.latencies {
+            let labels = format!("endpoint=\"{endpoint}\",method=\"{method}\"");
+            for (bucket, le) in histogram.buckets.iter().zip(LATENCY_BUCKETS_S) {
+                writeln!(
+                    out,
+                    "sim_request_duration_seconds_bucket{{{labels},le=\"{le}\"}} {bucket}"
+                )?;
+            }
+
+            writeln!(
+                out,
+                "sim_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            )?;
+            writeln!(
+                out,
+                "sim_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            )?;
+            writeln!(
+                out,
+                "sim_request_duration_seconds_count{{{labels}}} {}",
+                histogram.count
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP sim_customers Customers currently in each phase"
+        )?;
+        writeln!(out, "# TYPE sim_customers gauge")?;
+        for (status, count) in self.customers.lock().unwrap().iter() {
+            writeln!(out, "sim_customers{{status=\"{status:?}\"}} {count}")?;
+        }
+
+        writeln!(
+            out,
+            "# HELP sim_customers_spawned_total Customers that have entered the simulation"
+        )?;
+        writeln!(out, "# TYPE sim_customers_spawned_total counter")?;
+        writeln!(
+            out,
+            "sim_customers_spawned_total {}",
+            self.customers_spawned.lock().unwrap()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::record;
+
+    #[test]
+    fn prometheus_counts_requests_as_they_are_recorded() {
+        let metrics = Metrics::new();
+        metrics.record(record("/cargo/query", Some(200), 20.0));
+        metrics.record(record("/cargo/query", Some(500), 200.0));
+        metrics.record(record("/cargo/query", None, 2000.0));
+
+        let text = metrics.render_prometheus();
+        let labels = "endpoint=\"/cargo/query\",method=\"POST\"";
+        for line in [
+            format!("sim_requests_total{{{labels},status=\"200\"}} 1"),
+            format!("sim_requests_total{{{labels},status=\"none\"}} 1"),
+            format!("sim_request_errors_total{{{labels}}} 2"),
+            format!("sim_request_duration_seconds_bucket{{{labels},le=\"0.025\"}} 1"),
+            format!("sim_request_duration_seconds_bucket{{{labels},le=\"0.25\"}} 2"),
+            format!("sim_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3"),
+            format!("sim_request_duration_seconds_count{{{labels}}} 3"),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line}");
+        }
+    }
+
+    #[test]
+    fn customers_leaving_are_no_longer_counted() {
+        let metrics = Metrics::new();
+        metrics.customer_moved(None, CustomerStatus::Vertiports);
+        metrics.customer_moved(Some(CustomerStatus::Vertiports), CustomerStatus::Query);
+        metrics.customer_left(CustomerStatus::Query);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("sim_customers{status=\"Query\"} 0"));
+        assert!(text.contains("sim_customers_spawned_total 1"));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::metrics_types::RequestRecord;
+use chrono::{NaiveDateTime, Utc};
+
+/// The simulation time `s` seconds after the start of 2022
+pub fn at(s: i64) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp_opt(1_640_995_200 + s, 0).unwrap()
+}
+
+/// A POST request made at the start of 2022
+pub fn record(endpoint: &str, status: Option<u16>, latency_ms: f64) -> RequestRecord {
+    RequestRecord {
+        endpoint: endpoint.to_string(),
+        method: "POST".to_string(),
+        status,
+        latency_ms,
+        response_bytes: 0,
+        sim_time: at(0),
+        wall_time: Utc::now(),
+    }
+}
+
+/// Serves canned responses by path (404 for anything else) and
+///  returns a dispatcher that sends every request to it
+///
+/// Every target points at the same server.
+#[cfg(test)]
+pub fn stub_service(
+    responses: &'static [(&'static str, u16, &'static str)],
+) -> crate::event_types::Dispatcher {
+    use crate::event_types::{Dispatcher, Targets};
+    use crate::metrics_types::Metrics;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+
+    let make_service = make_service_fn(move |_| async move {
+        Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
+            let (status, body) = responses
+                .iter()
+                .find(|(path, ..)| req.uri().path() == *path)
+                .map(|(_, status, body)| (*status, *body))
+                .unwrap_or((404, ""));
+
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .status(status)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        }))
+    });
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    let targets = Targets {
+        cargo: url.clone(),
+        rideshare: url.clone(),
+        charter: url.clone(),
+        assets: url,
+        ..Targets::default()
+    };
+
+    Dispatcher::new(targets, Metrics::new())
+}